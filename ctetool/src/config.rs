@@ -0,0 +1,56 @@
+use crate::error::{CliError, ResultExt};
+use crate::{DitherArg, LumaArg};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAME: &str = "ctetool.toml";
+
+/// The default options read from a `ctetool.toml` file. The options given on the command line
+/// take precedence over them.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// the directory to write to, when extract or encode aren't given a single input and output
+    pub out_dir: Option<PathBuf>,
+    /// the format to encode to
+    pub format: Option<String>,
+    pub dither: Option<DitherArg>,
+    pub luma: Option<LumaArg>,
+    pub alpha_threshold: Option<u8>,
+    /// the number of files processed at the same time
+    pub jobs: Option<usize>,
+}
+
+impl Config {
+    /// the path of the configuration file used, if it exist. The one in the current directory
+    /// take precedence over the one in the user configuration directory.
+    fn find() -> Option<PathBuf> {
+        let local = PathBuf::from(CONFIG_FILE_NAME);
+        if local.is_file() {
+            return Some(local);
+        };
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        let user = config_dir.join("ctetool").join(CONFIG_FILE_NAME);
+        if user.is_file() {
+            Some(user)
+        } else {
+            None
+        }
+    }
+
+    /// load the configuration file, or use the default configuration if there isn't one
+    pub fn load() -> Result<Self, CliError> {
+        let path = match Self::find() {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        let content = fs::read_to_string(&path).for_file(&path)?;
+        toml::from_str(&content).map_err(|err| CliError::Config(path, err))
+    }
+}
@@ -0,0 +1,157 @@
+use image::ImageError;
+use pmd_cte::{CteDecodeError, CteEncodeError, ParseCteFormatError};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// An error that stop the processing of a file
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("failed to access {0:?}")]
+    Io(PathBuf, #[source] io::Error),
+    #[error("{0:?} isn't a valid cte file")]
+    BadCte(PathBuf, #[source] CteDecodeError),
+    #[error("{0:?} isn't a valid picture")]
+    BadPicture(PathBuf, #[source] ImageError),
+    #[error("{0:?} use a cte format that isn't supported")]
+    UnsupportedCteFormat(PathBuf, #[source] CteDecodeError),
+    #[error("{0:?} use a picture format that isn't supported")]
+    UnsupportedPictureFormat(PathBuf, #[source] ImageError),
+    #[error("{0:?} use the unknown cte format {1}")]
+    UnknownCteFormat(PathBuf, u32),
+    #[error("failed to encode {0:?}")]
+    Encode(PathBuf, #[source] CteEncodeError),
+    #[error("the output file {0:?} already exist")]
+    OutputExist(PathBuf),
+    #[error(transparent)]
+    UnknownFormatName(#[from] ParseCteFormatError),
+    #[error("{0}")]
+    Usage(String),
+    #[error("the configuration file {0:?} is invalid")]
+    Config(PathBuf, #[source] toml::de::Error),
+    #[error("the sidecar file {0:?} is invalid")]
+    Sidecar(PathBuf, #[source] toml::de::Error),
+    #[error("failed to watch {0:?}")]
+    Watch(PathBuf, #[source] notify::Error),
+    #[error("{0:?} has too many tiles ({1}) to put them in a single image")]
+    TooManyTiles(PathBuf, u64),
+    #[error("the glyph {1} of {0:?} is too far to be placed in an image")]
+    GlyphOutsideGrid(PathBuf, u32),
+    #[error("the round trip of {0:?} differ by up to {1}, more than the tolerance of {2}")]
+    VerificationFailed(PathBuf, u8, u8),
+    #[error("{0} of the files couldn't be processed")]
+    BatchFailed(usize),
+}
+
+impl CliError {
+    /// the exit code of the program when it fail with this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::OutputExist(_) | Self::BatchFailed(_) => 1,
+            Self::UnknownFormatName(_) | Self::Usage(_) | Self::Config(_, _) => 2,
+            Self::BadCte(_, _)
+            | Self::BadPicture(_, _)
+            | Self::Encode(_, _)
+            | Self::Sidecar(_, _)
+            | Self::TooManyTiles(_, _)
+            | Self::GlyphOutsideGrid(_, _) => 3,
+            Self::UnsupportedCteFormat(_, _)
+            | Self::UnsupportedPictureFormat(_, _)
+            | Self::UnknownCteFormat(_, _) => 4,
+            Self::Io(_, _) | Self::Watch(_, _) => 5,
+            Self::VerificationFailed(_, _, _) => 6,
+        }
+    }
+
+    /// a suggestion on how to fix the problem, if there is one
+    pub fn hint(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::BadCte(_, _) => "check that the file is a cte image and that it isn't truncated",
+            Self::UnsupportedCteFormat(_, _) | Self::UnknownCteFormat(_, _) => {
+                "use `ctetool inspect` to see the format of the file"
+            }
+            Self::UnsupportedPictureFormat(_, _) => "convert the picture to png",
+            Self::VerificationFailed(_, _, _) => {
+                "compressed formats like ETC1 rarely round trip exactly, --tolerance allow small differences"
+            }
+            Self::TooManyTiles(_, _) => "write each tile to its own image, without --strip",
+            Self::GlyphOutsideGrid(_, _) => {
+                "check the name of the glyph images, and the code points of the mapping file"
+            }
+            Self::Encode(_, CteEncodeError::WidthNotMultiple8(_))
+            | Self::Encode(_, CteEncodeError::HeightNotMultiple8(_)) => {
+                "resize the picture so its dimensions are multiples of 8, or use --pad with encode"
+            }
+            Self::Encode(_, CteEncodeError::AlphaDiscarded(_)) => {
+                "choose a format that store alpha with --format"
+            }
+            Self::OutputExist(_) => {
+                "use --force to overwrite it, or --skip-existing to skip the existing files"
+            }
+            Self::UnknownFormatName(_) => {
+                "the available formats are rgba8888, rgb888, rgba5551, rgb565, rgba4444, la8, hilo8, l8, a8, la4, l4, a4, etc1, etc1a4 and auto"
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// An error that can be converted to a [`CliError`] once the file it happened with is known
+pub trait IntoCliError {
+    fn into_cli_error(self, path: &Path) -> CliError;
+}
+
+impl IntoCliError for io::Error {
+    fn into_cli_error(self, path: &Path) -> CliError {
+        CliError::Io(path.to_path_buf(), self)
+    }
+}
+
+impl IntoCliError for CteDecodeError {
+    fn into_cli_error(self, path: &Path) -> CliError {
+        let path = path.to_path_buf();
+        match self {
+            CteDecodeError::IOError(err, _) if err.kind() != io::ErrorKind::UnexpectedEof => {
+                CliError::Io(path, err)
+            }
+            CteDecodeError::UnsuportedFormat(_, _)
+            | CteDecodeError::PixelLenghtInvalid(_, _, _) => {
+                CliError::UnsupportedCteFormat(path, self)
+            }
+            _ => CliError::BadCte(path, self),
+        }
+    }
+}
+
+impl IntoCliError for CteEncodeError {
+    fn into_cli_error(self, path: &Path) -> CliError {
+        match self {
+            CteEncodeError::IOError(err) => CliError::Io(path.to_path_buf(), err),
+            _ => CliError::Encode(path.to_path_buf(), self),
+        }
+    }
+}
+
+impl IntoCliError for ImageError {
+    fn into_cli_error(self, path: &Path) -> CliError {
+        let path = path.to_path_buf();
+        match self {
+            ImageError::IoError(err) => CliError::Io(path, err),
+            ImageError::Unsupported(_) => CliError::UnsupportedPictureFormat(path, self),
+            _ => CliError::BadPicture(path, self),
+        }
+    }
+}
+
+pub trait ResultExt<T> {
+    /// convert the error to a [`CliError`] that happened with the file at `path`
+    fn for_file(self, path: &Path) -> Result<T, CliError>;
+}
+
+impl<T, E: IntoCliError> ResultExt<T> for Result<T, E> {
+    fn for_file(self, path: &Path) -> Result<T, CliError> {
+        self.map_err(|err| err.into_cli_error(path))
+    }
+}
@@ -1,69 +1,1943 @@
-use clap::Parser;
-use image::io::Reader as ImageReader;
-use pmd_cte::{CteFormat, CteImage};
-use std::{fs::File, io::BufReader, path::PathBuf};
+mod config;
+use config::Config;
+mod error;
+use error::{CliError, ResultExt};
+mod output;
+use output::{emit, error_json, file_result_json, is_json, path_json};
+mod preview;
+use preview::Protocol;
+mod sidecar;
+use sidecar::Sidecar;
 
-/// ctetool can be used to encode or decode cte file (extension .img) from pokemon super mystery dungeon. It only support the font cte file.
+use clap::{ArgEnum, CommandFactory, Parser};
+use clap_complete::Shell;
+use image::{
+    imageops::FilterType, io::Reader as ImageReader, DynamicImage, GenericImage, GenericImageView,
+    GrayImage, ImageOutputFormat, Luma, Rgba, RgbaImage,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{info, log_enabled, warn, Level, LevelFilter};
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use pmd_cte::{
+    CteDecodeError, CteFormat, CteImage, CteTileReader, DecodeOptions, Dithering, EncodeOptions,
+    LumaConversion, QualityMetrics,
+};
+use rayon::prelude::*;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    convert::{TryFrom, TryInto},
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::Duration,
+};
+
+/// ctetool can be used to encode or decode cte file (extension .img) from pokemon super mystery dungeon, in any of the PICA200 texture formats (including ETC1), and to inspect, verify and convert them.
 #[derive(Parser)]
+#[clap(version)]
 struct Opts {
     #[clap(subcommand)]
     subcmd: SubCommand,
+    /// only print errors and warnings
+    #[clap(short, long, global = true)]
+    quiet: bool,
+    /// print debugging information, like the header of the files. Repeat to also print the data
+    /// of each tile.
+    #[clap(
+        short,
+        long,
+        global = true,
+        parse(from_occurrences),
+        conflicts_with = "quiet"
+    )]
+    verbose: u8,
+    /// fail on the cte files with an inconsistent header (the default)
+    #[clap(long, global = true)]
+    strict: bool,
+    /// try to decode the cte files with an inconsistent header anyway, printing a warning for
+    /// each problem
+    #[clap(long, global = true, conflicts_with = "strict")]
+    lenient: bool,
+    /// print the results as json objects, one per line, on the standard output
+    #[clap(long, global = true)]
+    json: bool,
 }
 
 #[derive(Parser)]
 enum SubCommand {
-    /// Extract a cte file to an image
+    /// Extract cte files to images
     Extract(ExtractParameter),
-    /// Encode an image to a cte file
+    /// Encode images to cte files
     Encode(EncodeParameter),
+    /// Print the information stored in the header of a cte file
+    Inspect(InspectParameter),
+    /// Extract every cte file of a directory, recursively, to png images
+    ExtractAll(ExtractAllParameter),
+    /// Encode every png image of a directory, recursively, to cte files
+    EncodeAll(EncodeAllParameter),
+    /// Check that a cte file is decoded to the same image after being re-encoded
+    Verify(VerifyParameter),
+    /// Compare the pixels of two images, each one being either a cte file or a picture
+    Diff(DiffParameter),
+    /// Convert a cte file to another cte format
+    Convert(ConvertParameter),
+    /// Write each 8×8 tile of a cte file to its own image, in the order they are stored in
+    Tiles(TilesParameter),
+    /// Write each glyph of a font cte file to its own image, the glyphs being laid out on a grid
+    SplitGlyphs(SplitGlyphsParameter),
+    /// Lay the glyph images written by split-glyphs on a grid, and encode it to a font cte file
+    BuildAtlas(BuildAtlasParameter),
+    /// Draw a cte file or a picture in the terminal
+    Preview(PreviewParameter),
+    /// Print an annotated hexdump of the header of a cte file and of the padding following it
+    Header(HeaderParameter),
+    /// Print statistics about the luminance, the alpha and the tiles of a cte file or picture
+    Stats(StatsParameter),
+    /// Print the sha256 hash of the decoded pixels of cte files, ignoring how they are stored
+    Hash(HashParameter),
+    /// Watch a directory, encoding the png images to cte files each time they are changed
+    Watch(WatchParameter),
+    /// Print the completion script of ctetool for a shell
+    Completions(CompletionsParameter),
+    /// Print the man page of ctetool, in the roff format
+    Manpage,
+}
+
+impl ExtractParameter {
+    fn merge(&mut self, config: &Config) {
+        if self.out_dir.is_none() && self.paths.len() != 2 {
+            self.out_dir = config.out_dir.clone();
+        };
+        self.batch.merge(config);
+    }
+}
+
+impl EncodeParameter {
+    fn merge(&mut self, config: &Config) {
+        if self.out_dir.is_none() && self.paths.len() != 2 {
+            self.out_dir = config.out_dir.clone();
+        };
+        self.default_format = config.format.clone();
+        self.quantization.merge(config);
+        self.batch.merge(config);
+    }
+}
+
+/// How multiple files are processed and written
+#[derive(Parser)]
+struct BatchParameter {
+    /// overwrite the output files that already exist
+    #[clap(long)]
+    force: bool,
+    /// don't process the inputs whose output file already exist
+    #[clap(long, conflicts_with = "force")]
+    skip_existing: bool,
+    /// only report the files that would be processed, and those that can't be read, without
+    /// writing anything
+    #[clap(long)]
+    dry_run: bool,
+    /// the number of files processed at the same time. Default to the number of processors.
+    #[clap(short, long)]
+    jobs: Option<usize>,
+}
+
+impl BatchParameter {
+    fn merge(&mut self, config: &Config) {
+        self.jobs = self.jobs.or(config.jobs);
+    }
+}
+
+#[derive(ArgEnum, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum DitherArg {
+    None,
+    Ordered,
+    Fs,
+}
+
+#[derive(ArgEnum, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum LumaArg {
+    Average,
+    Rec601,
+    Rec709,
+}
+
+/// How the pixels are quantized when encoding to a format with less precision
+#[derive(Parser)]
+struct QuantizationParameter {
+    /// the dithering used for the 4 bit formats (ordered use a 4×4 Bayer matrix, fs is
+    /// Floyd–Steinberg). Default to none.
+    #[clap(long, arg_enum)]
+    dither: Option<DitherArg>,
+    /// how colored pixels are converted to luminance. Default to average.
+    #[clap(long, arg_enum)]
+    luma: Option<LumaArg>,
+    /// make the pixels with an alpha at least this value fully opaque, and the other fully
+    /// transparent
+    #[clap(long)]
+    alpha_threshold: Option<u8>,
+}
+
+impl QuantizationParameter {
+    /// use the values of the configuration file for the options that weren't given
+    fn merge(&mut self, config: &Config) {
+        self.dither = self.dither.or(config.dither);
+        self.luma = self.luma.or(config.luma);
+        self.alpha_threshold = self.alpha_threshold.or(config.alpha_threshold);
+    }
+
+    fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            dithering: match self.dither.unwrap_or(DitherArg::None) {
+                DitherArg::None => Dithering::None,
+                DitherArg::Ordered => Dithering::Ordered(4),
+                DitherArg::Fs => Dithering::FloydSteinberg,
+            },
+            luma: match self.luma.unwrap_or(LumaArg::Average) {
+                LumaArg::Average => LumaConversion::Average,
+                LumaArg::Rec601 => LumaConversion::Rec601,
+                LumaArg::Rec709 => LumaConversion::Rec709,
+            },
+            alpha_threshold: self.alpha_threshold,
+            ..EncodeOptions::default()
+        }
+    }
 }
 
 #[derive(Parser)]
 struct ExtractParameter {
+    /// without --out-dir, the input .img cte file (or - for the standard input) followed by the
+    /// output file (format determined by extension, .png recommanded, or - to write a png to the
+    /// standard output). With --out-dir, the input files or glob patterns (like
+    /// 'romfs/**/*.img').
+    #[clap(required = true)]
+    paths: Vec<String>,
+    /// write a png image for each input file in this directory
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+    /// only write the alpha channel, as an opaque grayscale image
+    #[clap(long)]
+    alpha_only: bool,
+    /// also write the format and header settings of each file to a .cte.toml file next to its
+    /// image, which is used to encode it back identically
+    #[clap(long)]
+    sidecar: bool,
+    #[clap(flatten)]
+    batch: BatchParameter,
+}
+
+#[derive(Parser)]
+struct EncodeParameter {
+    /// without --out-dir, the input picture file (or - for the standard input) followed by the
+    /// output .img cte file (or - for the standard output). With --out-dir, the input files or
+    /// glob patterns (like 'images/**/*.png').
+    #[clap(required = true)]
+    paths: Vec<String>,
+    /// write a .img cte file for each input file in this directory
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+    #[clap(flatten)]
+    batch: BatchParameter,
+    /// the format to encode to (a8, la4, rgba4444, rgba8888...), or auto to use the smallest
+    /// format that keep the image mostly identical. Default to a8. Take precedence over the
+    /// format of the sidecar files.
+    #[clap(long)]
+    format: Option<String>,
+    /// the format of the configuration file, used when neither --format nor a sidecar file
+    /// give one
+    #[clap(skip)]
+    default_format: Option<String>,
+    #[clap(flatten)]
+    quantization: QuantizationParameter,
+    /// pad the images whose dimensions aren't multiples of 8, with transparent pixels (the
+    /// default) or by repeating the pixels of the edge
+    #[clap(
+        long,
+        arg_enum,
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "transparent"
+    )]
+    pad: Option<PadMode>,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum PadMode {
+    Transparent,
+    Edge,
+}
+
+#[derive(Parser)]
+struct InspectParameter {
     /// the input .img cte file
     input: PathBuf,
-    /// the output file (format determined by extension, .png recommanded)
+}
+
+#[derive(Parser)]
+struct ExtractAllParameter {
+    /// the directory containing the .img cte files
+    input: PathBuf,
+    /// the directory to write the images to, with the same structure as the input
     output: PathBuf,
+    /// only extract the files whose name match this pattern
+    #[clap(long, default_value = "*.img")]
+    pattern: String,
+    /// also write the format and header settings of each file to a .cte.toml file next to its
+    /// image, which is used to encode it back identically
+    #[clap(long)]
+    sidecar: bool,
+    #[clap(flatten)]
+    batch: BatchParameter,
 }
 
 #[derive(Parser)]
-struct EncodeParameter {
-    /// the input picture file
+struct EncodeAllParameter {
+    /// the directory containing the png images
+    input: PathBuf,
+    /// the directory to write the .img cte files to, with the same structure as the input
+    output: PathBuf,
+    /// the directory containing the original .img files, with the same structure as the input.
+    /// The format and header settings of the original file are reused when it exist, otherwise
+    /// the image is encoded to A8. Default to the input directory.
+    #[clap(long)]
+    originals: Option<PathBuf>,
+    #[clap(flatten)]
+    quantization: QuantizationParameter,
+    #[clap(flatten)]
+    batch: BatchParameter,
+}
+
+#[derive(Parser)]
+struct VerifyParameter {
+    /// the input .img cte file
+    input: PathBuf,
+    /// the maximum difference allowed for each channel of each pixel
+    #[clap(long, default_value = "0")]
+    tolerance: u8,
+}
+
+#[derive(Parser)]
+struct DiffParameter {
+    /// the first .img cte file or picture
+    a: PathBuf,
+    /// the second .img cte file or picture
+    b: PathBuf,
+    /// write an image showing the differing pixels in red to this file
+    #[clap(long)]
+    output: Option<PathBuf>,
+    /// overwrite the diff image if it already exist
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct ConvertParameter {
+    /// the input .img cte file
+    input: PathBuf,
+    /// the output .img cte file
+    output: PathBuf,
+    /// the format to convert to (a8, la4, rgba4444, rgba8888...), or auto to use the smallest
+    /// format that keep the image mostly identical
+    #[clap(long)]
+    format: String,
+    /// overwrite the output file if it already exist
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct TilesParameter {
+    /// the input .img cte file
+    input: PathBuf,
+    /// the directory to write the tiles to, or the image to write with --strip
+    output: PathBuf,
+    /// write all the tiles side by side in a single image instead
+    #[clap(long)]
+    strip: bool,
+    /// overwrite the output files if they already exist
+    #[clap(long)]
+    force: bool,
+}
+
+/// The size of a cell of a grid, written as `<width>x<height>`
+#[derive(Clone, Copy)]
+struct CellSize {
+    width: u32,
+    height: u32,
+}
+
+impl FromStr for CellSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("{:?} isn't a cell size like 12x12", s);
+        let (width, height) = s.split_once('x').ok_or_else(error)?;
+        let cell = Self {
+            width: width.parse().map_err(|_| error())?,
+            height: height.parse().map_err(|_| error())?,
+        };
+        if cell.width == 0 || cell.height == 0 {
+            return Err(error());
+        };
+        Ok(cell)
+    }
+}
+
+#[derive(Parser)]
+struct SplitGlyphsParameter {
+    /// the input .img cte file
+    input: PathBuf,
+    /// the directory to write the glyphs to
+    output: PathBuf,
+    /// the size of each cell of the grid, like 12x12
+    #[clap(long)]
+    cell: CellSize,
+    /// a text file with a line `<index> <code point>` for each glyph (the code point being in
+    /// hexadecimal, like `41` or `U+0041`). The glyphs are then named by their code point.
+    #[clap(long)]
+    mapping: Option<PathBuf>,
+    /// overwrite the output files if they already exist
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct BuildAtlasParameter {
+    /// the directory containing the glyph images, named like glyph_0012.png or, with --mapping,
+    /// u0041.png
     input: PathBuf,
     /// the output .img cte file
     output: PathBuf,
+    /// the size of each cell of the grid, like 12x12
+    #[clap(long)]
+    cell: CellSize,
+    /// the number of cells of each row of the grid
+    #[clap(long)]
+    columns: u32,
+    /// the mapping file used to split the font, to find the index of the glyphs named by their
+    /// code point
+    #[clap(long)]
+    mapping: Option<PathBuf>,
+    /// the format to encode to (a8, la4, rgba4444, rgba8888...), or auto to use the smallest
+    /// format that keep the image mostly identical. Default to a8.
+    #[clap(long)]
+    format: Option<String>,
+    #[clap(flatten)]
+    quantization: QuantizationParameter,
+    /// overwrite the output file if it already exist
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct PreviewParameter {
+    /// the input .img cte file or picture
+    input: PathBuf,
+    /// how the image is drawn
+    #[clap(long, arg_enum, default_value = "auto")]
+    protocol: Protocol,
+    /// enlarge the image by this factor. With blocks, the image is shrunk to fit the terminal
+    /// if needed.
+    #[clap(long, default_value = "1")]
+    scale: u32,
+}
+
+#[derive(Parser)]
+struct HeaderParameter {
+    /// the input .img cte file
+    input: PathBuf,
+}
+
+#[derive(Parser)]
+struct StatsParameter {
+    /// the input .img cte file or picture
+    input: PathBuf,
+}
+
+#[derive(Parser)]
+struct HashParameter {
+    /// the input .img cte files, or glob patterns (like 'romfs/**/*.img')
+    #[clap(required = true)]
+    paths: Vec<String>,
+}
+
+#[derive(Parser)]
+struct WatchParameter {
+    /// the directory containing the png images
+    input: PathBuf,
+    /// the directory to write the .img cte files to, with the same structure as the input
+    output: PathBuf,
+    /// the format to encode to (a8, la4, rgba4444, rgba8888...), or auto to use the smallest
+    /// format that keep the image mostly identical. Default to a8. Take precedence over the
+    /// format of the sidecar files.
+    #[clap(long)]
+    format: Option<String>,
+    /// the format of the configuration file, used when neither --format nor a sidecar file
+    /// give one
+    #[clap(skip)]
+    default_format: Option<String>,
+    #[clap(flatten)]
+    quantization: QuantizationParameter,
+}
+
+#[derive(Parser)]
+struct CompletionsParameter {
+    /// the shell to generate the completion script for
+    #[clap(arg_enum)]
+    shell: Shell,
 }
 
 fn main() {
     let opts = Opts::parse();
+    let level = match (opts.quiet, opts.verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "{}", record.args()),
+            level => writeln!(buf, "[{}] {}", level.as_str().to_lowercase(), record.args()),
+        })
+        .init();
+    output::set_json(opts.json);
+    LENIENT.store(opts.lenient, Ordering::Relaxed);
 
-    match opts.subcmd {
-        SubCommand::Extract(ep) => extract(ep),
-        SubCommand::Encode(ep) => encode(ep),
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            report_error(&err);
+            std::process::exit(err.exit_code());
+        }
+    };
+    let result = match opts.subcmd {
+        SubCommand::Extract(mut ep) => {
+            ep.merge(&config);
+            extract(ep)
+        }
+        SubCommand::Encode(mut ep) => {
+            ep.merge(&config);
+            encode(ep)
+        }
+        SubCommand::Inspect(ip) => inspect(ip),
+        SubCommand::ExtractAll(mut ep) => {
+            ep.batch.merge(&config);
+            extract_all(ep)
+        }
+        SubCommand::EncodeAll(mut ep) => {
+            ep.quantization.merge(&config);
+            ep.batch.merge(&config);
+            encode_all(ep, config.format.as_deref().unwrap_or("a8"))
+        }
+        SubCommand::Verify(vp) => verify(vp),
+        SubCommand::Diff(dp) => diff(dp),
+        SubCommand::Convert(cp) => convert(cp),
+        SubCommand::Tiles(tp) => tiles(tp),
+        SubCommand::SplitGlyphs(sp) => split_glyphs(sp),
+        SubCommand::BuildAtlas(mut bp) => {
+            bp.format = bp.format.take().or_else(|| config.format.clone());
+            bp.quantization.merge(&config);
+            build_atlas(bp)
+        }
+        SubCommand::Preview(pp) => preview(pp),
+        SubCommand::Header(hp) => header(hp),
+        SubCommand::Stats(sp) => stats(sp),
+        SubCommand::Hash(hp) => hash(hp),
+        SubCommand::Watch(mut wp) => {
+            wp.default_format = config.format.clone();
+            wp.quantization.merge(&config);
+            watch(wp)
+        }
+        SubCommand::Completions(cp) => {
+            clap_complete::generate(cp.shell, &mut Opts::command(), "ctetool", &mut io::stdout());
+            Ok(())
+        }
+        SubCommand::Manpage => clap_mangen::Man::new(Opts::command())
+            .render(&mut io::stdout())
+            .for_file(Path::new("-")),
+    };
+    if let Err(err) = result {
+        report_error(&err);
+        std::process::exit(err.exit_code());
     };
 }
 
-fn extract(param: ExtractParameter) {
-    println!(
-        "extracting the file {:?} to {:?}",
-        param.input, param.output
+/// whether the cte files are decoded in lenient mode, with `--lenient`
+static LENIENT: AtomicBool = AtomicBool::new(false);
+
+/// decode a cte file and its mipmaps, in lenient mode if it is enabled (the library logging the
+/// problems it ignored)
+fn decode_cte(path: &Path, input: &mut impl Read) -> Result<CteImage, CliError> {
+    let options = DecodeOptions {
+        strict: !LENIENT.load(Ordering::Relaxed),
+        mipmaps: true,
+        ..DecodeOptions::default()
+    };
+    CteImage::decode_cte_with(input, &options).for_file(path)
+}
+
+/// print the error, what caused it, and how to fix it
+fn report_error(err: &CliError) {
+    if is_json() {
+        emit(serde_json::json!({ "error": error_json(err) }));
+        return;
+    };
+    eprintln!("error: {}", err);
+    let mut source = err.source();
+    while let Some(cause) = source {
+        eprintln!("  caused by: {}", cause);
+        source = cause.source();
+    }
+    if let Some(hint) = err.hint() {
+        eprintln!("  hint: {}", hint);
+    };
+}
+
+/// open the file at `path` for reading, or the standard input if it is `-`
+fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// create the file at `path` for writing, or use the standard output if it is `-`
+fn create_output(path: &Path) -> io::Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdout()))
+    } else {
+        Ok(Box::new(File::create(path)?))
+    }
+}
+
+/// the files matching each pattern, with their path relative to the part of the pattern before
+/// the first wildcard. Patterns without wildcard are kept as is, even if the file doesn't exist.
+fn expand_patterns(patterns: &[String]) -> Result<Vec<(PathBuf, PathBuf)>, CliError> {
+    let mut result = Vec::new();
+    for pattern in patterns {
+        let path = PathBuf::from(pattern);
+        let base: PathBuf = path
+            .components()
+            .take_while(|c| {
+                !c.as_os_str()
+                    .to_string_lossy()
+                    .contains(&['*', '?', '['][..])
+            })
+            .collect();
+        if base == path {
+            let file_name = PathBuf::from(path.file_name().unwrap_or_default());
+            result.push((path, file_name));
+            continue;
+        };
+        let mut files: Vec<PathBuf> = glob::glob(pattern)
+            .map_err(|err| CliError::Usage(format!("invalid pattern {:?}: {}", pattern, err)))?
+            .filter_map(Result::ok)
+            .filter(|file| file.is_file())
+            .collect();
+        files.sort();
+        for file in files {
+            let relative = file.strip_prefix(&base).unwrap().to_path_buf();
+            result.push((file, relative));
+        }
+    }
+    Ok(result)
+}
+
+/// check whether `output` can be written to. Return false if it should be skipped.
+fn check_output(output: &Path, param: &BatchParameter) -> Result<bool, CliError> {
+    if output == Path::new("-") || param.force || !output.exists() {
+        Ok(true)
+    } else if param.skip_existing {
+        Ok(false)
+    } else {
+        Err(CliError::OutputExist(output.to_path_buf()))
+    }
+}
+
+/// apply `action` to each pair of input and output files in parallel, continuing past failures,
+/// then print a summary. In dry run mode, only `check` is applied to the input files.
+fn run_batch<C, F>(
+    files: &[(PathBuf, PathBuf)],
+    action_name: &str,
+    param: &BatchParameter,
+    check: C,
+    action: F,
+) -> Result<(), CliError>
+where
+    C: Fn(&Path) -> Result<(), CliError> + Sync,
+    F: Fn(&Path, &Path) -> Result<(), CliError> + Sync,
+{
+    let prefix = if param.dry_run { "[dry run] " } else { "" };
+    let progress = if log_enabled!(Level::Info) {
+        ProgressBar::new(files.len() as u64)
+    } else {
+        ProgressBar::hidden()
+    };
+    progress.set_style(
+        ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos}/{len} (eta {eta}) {msg}")
+            .unwrap(),
+    );
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(param.jobs.unwrap_or(0))
+        .build()
+        .map_err(|err| CliError::Usage(format!("failed to start the worker threads: {}", err)))?;
+    // None if the file was skipped, otherwise whether it was processed successfully
+    let outcomes: Vec<Option<bool>> = pool.install(|| {
+        files
+            .par_iter()
+            .map(|(input, output)| {
+                progress.set_message(input.display().to_string());
+                let result = match check_output(output, param) {
+                    Ok(false) => {
+                        progress.suspend(|| {
+                            info!(
+                                "{}skipping {:?}, as {:?} already exist",
+                                prefix, input, output
+                            )
+                        });
+                        if is_json() {
+                            emit(file_result_json(input, output, None));
+                        };
+                        progress.inc(1);
+                        return None;
+                    }
+                    Ok(true) => {
+                        progress.suspend(|| {
+                            info!("{}{} {:?} to {:?}", prefix, action_name, input, output)
+                        });
+                        if param.dry_run {
+                            check(input)
+                        } else {
+                            action(input, output)
+                        }
+                    }
+                    Err(err) => Err(err),
+                };
+                if is_json() {
+                    emit(file_result_json(input, output, Some(&result)));
+                } else if let Err(err) = &result {
+                    progress.suspend(|| report_error(err));
+                };
+                progress.inc(1);
+                Some(result.is_ok())
+            })
+            .collect()
+    });
+    progress.finish_and_clear();
+    let failed: Vec<_> = files
+        .iter()
+        .zip(&outcomes)
+        .filter(|(_, outcome)| **outcome == Some(false))
+        .map(|((input, _), _)| input)
+        .collect();
+    let skipped = outcomes.iter().filter(|outcome| outcome.is_none()).count();
+    info!(
+        "{}processed {} files, {} skipped, {} failed",
+        prefix,
+        files.len() - failed.len() - skipped,
+        skipped,
+        failed.len()
+    );
+    for input in &failed {
+        info!("  {:?}", input);
+    }
+    if is_json() {
+        emit(serde_json::json!({
+            "dry_run": param.dry_run,
+            "processed": files.len() - failed.len() - skipped,
+            "skipped": skipped,
+            "failed": failed.len(),
+        }));
+    };
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::BatchFailed(failed.len()))
+    }
+}
+
+/// the input and output file of a command not using --out-dir
+fn single_paths(paths: &[String]) -> Result<(&Path, &Path), CliError> {
+    if paths.len() != 2 {
+        return Err(CliError::Usage(
+            "expected an input and an output file (use --out-dir for multiple inputs)".to_string(),
+        ));
+    };
+    Ok((Path::new(&paths[0]), Path::new(&paths[1])))
+}
+
+fn extract(param: ExtractParameter) -> Result<(), CliError> {
+    if let Some(out_dir) = &param.out_dir {
+        let files: Vec<_> = expand_patterns(&param.paths)?
+            .into_iter()
+            .map(|(input, relative)| (input, out_dir.join(relative).with_extension("png")))
+            .collect();
+        return run_batch(
+            &files,
+            "extracting",
+            &param.batch,
+            check_cte_file,
+            |input, output| extract_file(input, output, param.sidecar, param.alpha_only),
+        );
+    };
+    let (input_path, output_path) = single_paths(&param.paths)?;
+    if !check_output(output_path, &param.batch)? {
+        info!("{:?} already exist, skipping", output_path);
+        if is_json() {
+            emit(file_result_json(input_path, output_path, None));
+        };
+        return Ok(());
+    };
+    if param.batch.dry_run {
+        info!("[dry run] extracting {:?} to {:?}", input_path, output_path);
+        return check_cte_file(input_path);
+    };
+    info!("extracting the file {:?} to {:?}", input_path, output_path);
+    let mut input = open_input(input_path).for_file(input_path)?;
+    let cte_image = decode_cte(input_path, &mut input)?;
+    if param.sidecar && output_path != Path::new("-") {
+        Sidecar::from_image(&cte_image).write(&Sidecar::path_for(output_path))?;
+    };
+    let image = extracted_image(cte_image, param.alpha_only);
+    if output_path == Path::new("-") {
+        let mut output = Vec::new();
+        image
+            .write_to(&mut output, ImageOutputFormat::Png)
+            .for_file(output_path)?;
+        io::stdout().write_all(&output).for_file(output_path)?;
+    } else {
+        image.save(output_path).for_file(output_path)?;
+    };
+    info!("done !");
+    if is_json() {
+        emit(file_result_json(input_path, output_path, Some(&Ok(()))));
+    };
+    Ok(())
+}
+
+/// pad the image to a multiple of 8 according to `pad`, either directly or by setting
+/// `options.pad`
+fn pad_image(
+    image: DynamicImage,
+    pad: Option<PadMode>,
+    options: &mut EncodeOptions,
+) -> DynamicImage {
+    match pad {
+        None => image,
+        Some(PadMode::Transparent) => {
+            options.pad = true;
+            image
+        }
+        Some(PadMode::Edge) => {
+            let (width, height) = (image.width(), image.height());
+            let (padded_width, padded_height) = (width.div_ceil(8) * 8, height.div_ceil(8) * 8);
+            if (padded_width, padded_height) == (width, height) {
+                return image;
+            };
+            let image = image.into_rgba8();
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(padded_width, padded_height, |x, y| {
+                *image.get_pixel(x.min(width - 1), y.min(height - 1))
+            }))
+        }
+    }
+}
+
+/// the format named `name`, or the one chosen for the image if it is `auto`
+fn choose_format(name: &str, image: &DynamicImage) -> Result<CteFormat, CliError> {
+    if name == "auto" {
+        Ok(CteFormat::choose_for(image))
+    } else {
+        Ok(name.parse()?)
+    }
+}
+
+/// the format to encode `image` to: the one named with --format, otherwise the one of the
+/// original file or sidecar file at `stored.1` if there is one, otherwise `default_name`
+fn encoding_format(
+    name: Option<&str>,
+    default_name: &str,
+    stored: Option<(CteFormat, &Path)>,
+    image: &DynamicImage,
+) -> Result<CteFormat, CliError> {
+    match (name, stored) {
+        (Some(name), Some((stored, path))) => {
+            let format = choose_format(name, image)?;
+            if format != stored {
+                warn!(
+                    "encoding to {:?} as asked with --format, instead of the {:?} format of {:?}",
+                    format, stored, path
+                );
+            };
+            Ok(format)
+        }
+        (Some(name), None) => choose_format(name, image),
+        (None, Some((stored, _))) => Ok(stored),
+        (None, None) => choose_format(default_name, image),
+    }
+}
+
+fn encode(param: EncodeParameter) -> Result<(), CliError> {
+    let format_name = param.format.as_deref();
+    let default_format = param.default_format.as_deref().unwrap_or("a8");
+    let options = param.quantization.encode_options();
+    if let Some(out_dir) = &param.out_dir {
+        let files: Vec<_> = expand_patterns(&param.paths)?
+            .into_iter()
+            .map(|(input, relative)| (input, out_dir.join(relative).with_extension("img")))
+            .collect();
+        return run_batch(
+            &files,
+            "encoding",
+            &param.batch,
+            check_picture_file,
+            |input, output| {
+                let format = (format_name, default_format);
+                encode_file(input, output, format, None, param.pad, &options)
+            },
+        );
+    };
+    let (input_path, output_path) = single_paths(&param.paths)?;
+    if !check_output(output_path, &param.batch)? {
+        info!("{:?} already exist, skipping", output_path);
+        if is_json() {
+            emit(file_result_json(input_path, output_path, None));
+        };
+        return Ok(());
+    };
+    if param.batch.dry_run {
+        info!("[dry run] encoding {:?} into {:?}", input_path, output_path);
+        return check_picture_file(input_path);
+    };
+    let image = if input_path == Path::new("-") {
+        let mut data = Vec::new();
+        io::stdin().read_to_end(&mut data).for_file(input_path)?;
+        image::load_from_memory(&data).for_file(input_path)?
+    } else {
+        ImageReader::open(input_path)
+            .for_file(input_path)?
+            .decode()
+            .for_file(input_path)?
+    };
+    let mut options = options;
+    let image = pad_image(image, param.pad, &mut options);
+    let sidecar = if input_path == Path::new("-") {
+        None
+    } else {
+        Sidecar::read_for(input_path)?
+    };
+    let sidecar_path = Sidecar::path_for(input_path);
+    let stored = match sidecar {
+        Some(sidecar) => Some((sidecar.apply(&image, &mut options)?, sidecar_path.as_path())),
+        None => None,
+    };
+    let format = encoding_format(format_name, default_format, stored, &image)?;
+    info!(
+        "encoding {:?} into {:?} (using the {:?} encoding)",
+        input_path, output_path, format
+    );
+    let cte_image = CteImage::builder(image, format)
+        .allow_padding(options.pad)
+        .build()
+        .for_file(input_path)?;
+    let mut output = create_output(output_path).for_file(output_path)?;
+    cte_image
+        .encode_cte_with(&mut output, &options)
+        .for_file(output_path)?;
+    output.flush().for_file(output_path)?;
+    info!("done");
+    if is_json() {
+        emit(file_result_json(input_path, output_path, Some(&Ok(()))));
+    };
+    Ok(())
+}
+
+fn inspect(param: InspectParameter) -> Result<(), CliError> {
+    let file_size = fs::metadata(&param.input).for_file(&param.input)?.len();
+    let mut in_file = BufReader::new(File::open(&param.input).for_file(&param.input)?);
+    let header = CteImage::decode_header(&mut in_file).for_file(&param.input)?;
+    let format = header
+        .format()
+        .map(|format| format!("{:?}", format))
+        .unwrap_or_else(|| "unknown".to_string());
+    if is_json() {
+        emit(serde_json::json!({
+            "format": format,
+            "format_id": header.format_id,
+            "width": header.width,
+            "height": header.height,
+            "bits_per_pixel": header.pixel_length,
+            "pixel_start_offset": header.pixel_start_offset,
+            "unk": header.unk,
+            "file_size": file_size,
+        }));
+    } else {
+        println!("format: {} (id {})", format, header.format_id);
+        println!("dimensions: {}x{}", header.width, header.height);
+        println!("bits per pixel: {}", header.pixel_length);
+        println!("pixel data offset: {}", header.pixel_start_offset);
+        println!("unknown field: {}", header.unk);
+        println!("file size: {}", file_size);
+    }
+    Ok(())
+}
+
+/// list the files in `dir` and its subdirectories whose name match `pattern`
+fn find_files(
+    dir: &Path,
+    pattern: &glob::Pattern,
+    result: &mut Vec<PathBuf>,
+) -> Result<(), CliError> {
+    for entry in fs::read_dir(dir).for_file(dir)? {
+        let path = entry.for_file(dir)?.path();
+        if path.is_dir() {
+            find_files(&path, pattern, result)?;
+        } else if path
+            .file_name()
+            .map(|name| pattern.matches(&name.to_string_lossy()))
+            .unwrap_or(false)
+        {
+            result.push(path);
+        };
+    }
+    Ok(())
+}
+
+/// check that the header of the cte file at `path` can be read, and use a known format
+fn check_cte_file(path: &Path) -> Result<(), CliError> {
+    let mut file = open_input(path).for_file(path)?;
+    let header = CteImage::decode_header(&mut file).for_file(path)?;
+    if header.format().is_none() {
+        return Err(CliError::UnknownCteFormat(
+            path.to_path_buf(),
+            header.format_id,
+        ));
+    };
+    Ok(())
+}
+
+/// check that the header of the picture at `path` can be read
+fn check_picture_file(path: &Path) -> Result<(), CliError> {
+    ImageReader::open(path)
+        .for_file(path)?
+        .with_guessed_format()
+        .for_file(path)?
+        .into_dimensions()
+        .for_file(path)?;
+    Ok(())
+}
+
+/// create the directory the file at `path` will be written in
+fn create_parent_dir(path: &Path) -> Result<(), CliError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).for_file(parent)?;
+    };
+    Ok(())
+}
+
+/// the image to write when extracting, which is the alpha channel as a grayscale image if
+/// `alpha_only` is set
+fn extracted_image(cte_image: CteImage, alpha_only: bool) -> DynamicImage {
+    let image = cte_image.image.into_rgba8();
+    if alpha_only {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(image.width(), image.height(), |x, y| {
+            Luma([image.get_pixel(x, y).0[3]])
+        }))
+    } else {
+        DynamicImage::ImageRgba8(image)
+    }
+}
+
+fn extract_file(
+    input: &Path,
+    output: &Path,
+    sidecar: bool,
+    alpha_only: bool,
+) -> Result<(), CliError> {
+    let mut in_file = BufReader::new(File::open(input).for_file(input)?);
+    let cte_image = decode_cte(input, &mut in_file)?;
+    create_parent_dir(output)?;
+    if sidecar {
+        Sidecar::from_image(&cte_image).write(&Sidecar::path_for(output))?;
+    };
+    extracted_image(cte_image, alpha_only)
+        .save(output)
+        .for_file(output)?;
+    Ok(())
+}
+
+fn glob_pattern(pattern: &str) -> Result<glob::Pattern, CliError> {
+    glob::Pattern::new(pattern)
+        .map_err(|err| CliError::Usage(format!("invalid pattern {:?}: {}", pattern, err)))
+}
+
+fn extract_all(param: ExtractAllParameter) -> Result<(), CliError> {
+    let pattern = glob_pattern(&param.pattern)?;
+    let mut inputs = Vec::new();
+    find_files(&param.input, &pattern, &mut inputs)?;
+    inputs.sort();
+    let files: Vec<_> = inputs
+        .into_iter()
+        .map(|input| {
+            let relative = input.strip_prefix(&param.input).unwrap();
+            let output = param.output.join(relative).with_extension("png");
+            (input, output)
+        })
+        .collect();
+    run_batch(
+        &files,
+        "extracting",
+        &param.batch,
+        check_cte_file,
+        |input, output| extract_file(input, output, param.sidecar, false),
+    )
+}
+
+/// encode an image with the format and header settings of `original` if it is an existing file,
+/// or of the sidecar file of the image if there is one. `format` is the name of the format given
+/// with --format, which take precedence over them, and the name of the default format.
+fn encode_file(
+    input: &Path,
+    output: &Path,
+    format: (Option<&str>, &str),
+    original: Option<&Path>,
+    pad: Option<PadMode>,
+    options: &EncodeOptions,
+) -> Result<(), CliError> {
+    let mut options = options.clone();
+    let image = ImageReader::open(input)
+        .for_file(input)?
+        .decode()
+        .for_file(input)?;
+    let image = pad_image(image, pad, &mut options);
+    let sidecar_path = Sidecar::path_for(input);
+    let mut stored = None;
+    if let Some(original) = original.filter(|original| original.is_file()) {
+        let mut original_file = BufReader::new(File::open(original).for_file(original)?);
+        let header = CteImage::decode_header(&mut original_file).for_file(original)?;
+        let original_format = header
+            .format()
+            .ok_or_else(|| CliError::UnknownCteFormat(original.to_path_buf(), header.format_id))?;
+        stored = Some((original_format, original));
+        options.unk = Some(header.unk);
+        options.pixel_start_offset = header.pixel_start_offset;
+    } else if let Some(sidecar) = Sidecar::read_for(input)? {
+        stored = Some((sidecar.apply(&image, &mut options)?, sidecar_path.as_path()));
+    };
+    let format = encoding_format(format.0, format.1, stored, &image)?;
+    let cte_image = CteImage::builder(image, format)
+        .allow_padding(options.pad)
+        .build()
+        .for_file(input)?;
+    create_parent_dir(output)?;
+    let mut out_file = File::create(output).for_file(output)?;
+    cte_image
+        .encode_cte_with(&mut out_file, &options)
+        .for_file(output)?;
+    Ok(())
+}
+
+/// encode all the images, to `format_name` when there isn't an original file
+fn encode_all(param: EncodeAllParameter, format_name: &str) -> Result<(), CliError> {
+    let options = param.quantization.encode_options();
+    let pattern = glob_pattern("*.png")?;
+    let originals = param.originals.as_ref().unwrap_or(&param.input);
+    let mut inputs = Vec::new();
+    find_files(&param.input, &pattern, &mut inputs)?;
+    inputs.sort();
+    let files: Vec<_> = inputs
+        .into_iter()
+        .map(|input| {
+            let relative = input.strip_prefix(&param.input).unwrap();
+            let output = param.output.join(relative).with_extension("img");
+            (input, output)
+        })
+        .collect();
+    run_batch(
+        &files,
+        "encoding",
+        &param.batch,
+        check_picture_file,
+        |input, output| {
+            let relative = output.strip_prefix(&param.output).unwrap();
+            encode_file(
+                input,
+                output,
+                (None, format_name),
+                Some(&originals.join(relative)),
+                None,
+                &options,
+            )
+        },
+    )
+}
+
+fn verify(param: VerifyParameter) -> Result<(), CliError> {
+    let mut in_file = BufReader::new(File::open(&param.input).for_file(&param.input)?);
+    let cte_image = decode_cte(&param.input, &mut in_file)?;
+    let mut options = EncodeOptions::default();
+    if let Some(header) = &cte_image.header {
+        // in lenient mode, pixel data inside the header were decoded as starting just after it
+        options.pixel_start_offset = header.pixel_start_offset.max(28);
+    };
+    let mut encoded = Vec::new();
+    cte_image
+        .encode_cte_with(&mut encoded, &options)
+        .for_file(&param.input)?;
+    let decode_options = DecodeOptions {
+        mipmaps: true,
+        ..DecodeOptions::default()
+    };
+    let decoded_again = CteImage::decode_cte_with(&mut encoded.as_slice(), &decode_options)
+        .for_file(&param.input)?;
+    // the worst metrics of all the levels
+    let mut metrics = QualityMetrics::compare(&cte_image.image, &decoded_again.image);
+    for (mipmap, mipmap_again) in cte_image.mipmaps.iter().zip(&decoded_again.mipmaps) {
+        let level = QualityMetrics::compare(mipmap, mipmap_again);
+        metrics.max_error = metrics.max_error.max(level.max_error);
+        metrics.psnr = metrics.psnr.min(level.psnr);
+    }
+    let original_size = fs::metadata(&param.input).for_file(&param.input)?.len();
+    if is_json() {
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "size": encoded.len(),
+            "original_size": original_size,
+            "mipmaps": decoded_again.mipmaps.len(),
+            "max_error": metrics.max_error,
+            "psnr": metrics.psnr,
+            "within_tolerance": metrics.max_error <= param.tolerance,
+        }));
+    } else {
+        println!(
+            "re-encoded file is {} bytes long (original is {} bytes long)",
+            encoded.len(),
+            original_size
+        );
+        println!(
+            "max channel difference: {}, psnr: {:.2} dB",
+            metrics.max_error, metrics.psnr
+        );
+    };
+    if metrics.max_error > param.tolerance {
+        return Err(CliError::VerificationFailed(
+            param.input,
+            metrics.max_error,
+            param.tolerance,
+        ));
+    };
+    if !is_json() {
+        if metrics.max_error == 0 {
+            println!("the round trip is pixel-identical");
+        } else {
+            println!("the round trip is within the tolerance");
+        };
+    };
+    Ok(())
+}
+
+/// load either a cte file (recognized by its magic) or a picture
+fn load_image(path: &Path) -> Result<DynamicImage, CliError> {
+    let data = fs::read(path).for_file(path)?;
+    if data.starts_with(b"\0cte") {
+        Ok(decode_cte(path, &mut data.as_slice())?.image)
+    } else {
+        image::load_from_memory(&data).for_file(path)
+    }
+}
+
+fn diff(param: DiffParameter) -> Result<(), CliError> {
+    let a = load_image(&param.a)?.into_rgba8();
+    let b = load_image(&param.b)?.into_rgba8();
+    if a.dimensions() != b.dimensions() && !is_json() {
+        println!(
+            "the images have different dimensions ({:?} and {:?}), only comparing the overlapping part",
+            a.dimensions(),
+            b.dimensions()
+        );
+    };
+    let (width, height) = (a.width().min(b.width()), a.height().min(b.height()));
+    let mut differing_pixels = 0;
+    let mut max_delta = 0;
+    let mut diff_image = RgbaImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let (pa, pb) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+            let delta = (0..4)
+                .map(|c| (pa[c] as i32 - pb[c] as i32).unsigned_abs() as u8)
+                .max()
+                .unwrap();
+            if delta != 0 {
+                differing_pixels += 1;
+                diff_image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            } else {
+                // the identical pixels are drawn darkened, so the differences stand out
+                let luma = (pa[0] as u32 + pa[1] as u32 + pa[2] as u32) / 3;
+                let gray = (luma * pa[3] as u32 / 255 / 2) as u8;
+                diff_image.put_pixel(x, y, Rgba([gray, gray, gray, 255]));
+            };
+            max_delta = max_delta.max(delta);
+        }
+    }
+    if is_json() {
+        emit(serde_json::json!({
+            "a": path_json(&param.a),
+            "b": path_json(&param.b),
+            "a_dimensions": a.dimensions(),
+            "b_dimensions": b.dimensions(),
+            "differing_pixels": differing_pixels,
+            "compared_pixels": width * height,
+            "max_delta": max_delta,
+        }));
+    } else {
+        println!(
+            "{} of {} pixels differ, max channel difference: {}",
+            differing_pixels,
+            width * height,
+            max_delta
+        );
+    };
+    if let Some(output) = param.output {
+        let batch = BatchParameter {
+            force: param.force,
+            skip_existing: false,
+            dry_run: false,
+            jobs: None,
+        };
+        check_output(&output, &batch)?;
+        diff_image.save(&output).for_file(&output)?;
+    };
+    Ok(())
+}
+
+fn convert(param: ConvertParameter) -> Result<(), CliError> {
+    let batch = BatchParameter {
+        force: param.force,
+        skip_existing: false,
+        dry_run: false,
+        jobs: None,
+    };
+    check_output(&param.output, &batch)?;
+    let mut in_file = BufReader::new(File::open(&param.input).for_file(&param.input)?);
+    let cte_image = decode_cte(&param.input, &mut in_file)?;
+    let format = choose_format(&param.format, &cte_image.image)?;
+    info!(
+        "converting {:?} from {:?} to {:?}",
+        param.input, cte_image.original_format, format
     );
-    let mut in_file = BufReader::new(File::open(&param.input).unwrap());
-    let cte_image = CteImage::decode_cte(&mut in_file).unwrap();
-    cte_image.image.into_rgba8().save(&param.output).unwrap();
-    println!("done !");
+    let (converted, lossy) = cte_image.convert_to(format).for_file(&param.input)?;
+    if lossy {
+        warn!("the conversion to {:?} lost some information", format);
+    };
+    let mut options = EncodeOptions::default();
+    if let Some(header) = &cte_image.header {
+        options.unk = Some(header.unk);
+        options.pixel_start_offset = header.pixel_start_offset;
+    };
+    let mut out_file = File::create(&param.output).for_file(&param.output)?;
+    converted
+        .encode_cte_with(&mut out_file, &options)
+        .for_file(&param.output)?;
+    info!("done");
+    if is_json() {
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "output": path_json(&param.output),
+            "original_format": format!("{:?}", cte_image.original_format),
+            "format": format!("{:?}", format),
+            "lossy": lossy,
+        }));
+    };
+    Ok(())
+}
+
+fn tiles(param: TilesParameter) -> Result<(), CliError> {
+    let batch = BatchParameter {
+        force: param.force,
+        skip_existing: false,
+        dry_run: false,
+        jobs: None,
+    };
+    let in_file = BufReader::new(File::open(&param.input).for_file(&param.input)?);
+    let reader = CteTileReader::new(in_file).for_file(&param.input)?;
+    let tile_count = reader.tile_count();
+    let mut strip = if param.strip {
+        let too_many_tiles = || CliError::TooManyTiles(param.input.clone(), tile_count);
+        let width = u32::try_from(tile_count)
+            .ok()
+            .and_then(|count| count.checked_mul(8))
+            .ok_or_else(too_many_tiles)?;
+        // the image crate panic if the size of the buffer overflow
+        (width as usize)
+            .checked_mul(8 * 4)
+            .ok_or_else(too_many_tiles)?;
+        Some(RgbaImage::new(width, 8))
+    } else {
+        fs::create_dir_all(&param.output).for_file(&param.output)?;
+        None
+    };
+    for (index, tile) in reader.enumerate() {
+        let tile = tile.for_file(&param.input)?;
+        let tile_image = RgbaImage::from_fn(8, 8, |x, y| Rgba(tile.get_pixel(x, y)));
+        if let Some(strip) = &mut strip {
+            strip
+                .copy_from(&tile_image, index as u32 * 8, 0)
+                .expect("the strip should fit every tile");
+        } else {
+            let output = param
+                .output
+                .join(format!("tile_{:04}_{}_{}.png", index, tile.x, tile.y));
+            check_output(&output, &batch)?;
+            tile_image.save(&output).for_file(&output)?;
+        };
+    }
+    if let Some(strip) = strip {
+        check_output(&param.output, &batch)?;
+        strip.save(&param.output).for_file(&param.output)?;
+    };
+    info!("wrote {} tiles", tile_count);
+    if is_json() {
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "output": path_json(&param.output),
+            "tiles": tile_count,
+        }));
+    };
+    Ok(())
+}
+
+/// read a glyph mapping file, returning the code point of each glyph index
+fn read_glyph_mapping(path: &Path) -> Result<HashMap<u32, u32>, CliError> {
+    let content = fs::read_to_string(path).for_file(path)?;
+    let mut mapping = HashMap::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        };
+        let error = || {
+            CliError::Usage(format!(
+                "the line {} of the mapping file {:?} should be like `<index> <code point>`",
+                line_number + 1,
+                path
+            ))
+        };
+        let mut parts = line.split_whitespace();
+        let index = parts
+            .next()
+            .and_then(|index| index.parse().ok())
+            .ok_or_else(error)?;
+        let code_point = parts
+            .next()
+            .map(|code_point| code_point.trim_start_matches("U+"))
+            .and_then(|code_point| u32::from_str_radix(code_point, 16).ok())
+            .ok_or_else(error)?;
+        if parts.next().is_some() {
+            return Err(error());
+        };
+        mapping.insert(index, code_point);
+    }
+    Ok(mapping)
+}
+
+fn split_glyphs(param: SplitGlyphsParameter) -> Result<(), CliError> {
+    let batch = BatchParameter {
+        force: param.force,
+        skip_existing: false,
+        dry_run: false,
+        jobs: None,
+    };
+    let mapping = match &param.mapping {
+        Some(path) => Some(read_glyph_mapping(path)?),
+        None => None,
+    };
+    let image = load_image(&param.input)?.into_rgba8();
+    let columns = image.width() / param.cell.width;
+    let rows = image.height() / param.cell.height;
+    if columns * param.cell.width != image.width() || rows * param.cell.height != image.height() {
+        warn!(
+            "the image is {}x{}, which isn't a multiple of the cell size, ignoring the last pixels",
+            image.width(),
+            image.height()
+        );
+    };
+    fs::create_dir_all(&param.output).for_file(&param.output)?;
+    for index in 0..columns * rows {
+        let (x, y) = (index % columns, index / columns);
+        let glyph = image::imageops::crop_imm(
+            &image,
+            x * param.cell.width,
+            y * param.cell.height,
+            param.cell.width,
+            param.cell.height,
+        )
+        .to_image();
+        let code_point = mapping.as_ref().and_then(|mapping| mapping.get(&index));
+        let name = match code_point {
+            Some(code_point) => format!("u{:04x}.png", code_point),
+            None => format!("glyph_{:04}.png", index),
+        };
+        let output = param.output.join(name);
+        check_output(&output, &batch)?;
+        glyph.save(&output).for_file(&output)?;
+    }
+    info!("wrote {} glyphs", columns * rows);
+    if is_json() {
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "output": path_json(&param.output),
+            "glyphs": columns * rows,
+        }));
+    };
+    Ok(())
+}
+
+/// the index of the glyph stored in the file named `name`, as written by split-glyphs
+fn glyph_index(name: &str, mapping: Option<&HashMap<u32, u32>>) -> Option<u32> {
+    let stem = name.strip_suffix(".png")?;
+    if let Some(index) = stem.strip_prefix("glyph_") {
+        return index.parse().ok();
+    };
+    let code_point = u32::from_str_radix(stem.strip_prefix('u')?, 16).ok()?;
+    mapping?
+        .iter()
+        .find(|(_, mapped)| **mapped == code_point)
+        .map(|(index, _)| *index)
+}
+
+fn build_atlas(param: BuildAtlasParameter) -> Result<(), CliError> {
+    let batch = BatchParameter {
+        force: param.force,
+        skip_existing: false,
+        dry_run: false,
+        jobs: None,
+    };
+    check_output(&param.output, &batch)?;
+    if param.columns == 0 {
+        return Err(CliError::Usage(
+            "--columns should be at least 1".to_string(),
+        ));
+    };
+    let mapping = match &param.mapping {
+        Some(path) => Some(read_glyph_mapping(path)?),
+        None => None,
+    };
+    let mut glyphs = Vec::new();
+    for entry in fs::read_dir(&param.input).for_file(&param.input)? {
+        let path = entry.for_file(&param.input)?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        match glyph_index(&name, mapping.as_ref()) {
+            Some(index) => glyphs.push((index, path)),
+            None => warn!("ignoring {:?}, which isn't named like a glyph", path),
+        };
+    }
+    if glyphs.is_empty() {
+        return Err(CliError::Usage(format!(
+            "there isn't any glyph image in {:?}",
+            param.input
+        )));
+    };
+    glyphs.sort();
+    let round_to_8 = |value: u32| value.div_ceil(8).checked_mul(8);
+    let atlas_width = param
+        .columns
+        .checked_mul(param.cell.width)
+        .and_then(round_to_8)
+        .ok_or_else(|| {
+            CliError::Usage("the rows of glyphs would be too wide for an image".to_string())
+        })?;
+    // the last glyph set the number of rows
+    let (last_index, last_path) = glyphs.last().expect("there is at least one glyph");
+    let atlas_height = last_index
+        .checked_add(1)
+        .map(|glyph_count| glyph_count.div_ceil(param.columns))
+        .and_then(|rows| rows.checked_mul(param.cell.height))
+        .and_then(round_to_8)
+        .ok_or_else(|| CliError::GlyphOutsideGrid(last_path.clone(), *last_index))?;
+    let mut atlas = RgbaImage::new(atlas_width, atlas_height);
+    for (index, path) in &glyphs {
+        let glyph = load_image(path)?.into_rgba8();
+        if glyph.width() > param.cell.width || glyph.height() > param.cell.height {
+            warn!(
+                "{:?} is bigger than the cell size, only keeping its top left part",
+                path
+            );
+        };
+        let glyph = image::imageops::crop_imm(&glyph, 0, 0, param.cell.width, param.cell.height);
+        // the dimensions of the atlas were checked, so the position of every glyph fit in it
+        image::imageops::replace(
+            &mut atlas,
+            &glyph,
+            index % param.columns * param.cell.width,
+            index / param.columns * param.cell.height,
+        );
+    }
+    let image = DynamicImage::ImageRgba8(atlas);
+    let format = choose_format(param.format.as_deref().unwrap_or("a8"), &image)?;
+    info!(
+        "encoding {} glyphs into {:?} (using the {:?} encoding)",
+        glyphs.len(),
+        param.output,
+        format
+    );
+    let cte_image = CteImage::builder(image, format)
+        .build()
+        .for_file(&param.input)?;
+    let mut out_file = File::create(&param.output).for_file(&param.output)?;
+    cte_image
+        .encode_cte_with(&mut out_file, &param.quantization.encode_options())
+        .for_file(&param.output)?;
+    if is_json() {
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "output": path_json(&param.output),
+            "glyphs": glyphs.len(),
+            "format": format!("{:?}", format),
+        }));
+    };
+    Ok(())
 }
 
-fn encode(param: EncodeParameter) {
+fn preview(param: PreviewParameter) -> Result<(), CliError> {
+    let mut image = load_image(&param.input)?.into_rgba8();
+    let protocol = match param.protocol {
+        Protocol::Auto => Protocol::detect(),
+        protocol => protocol,
+    };
+    let scale = param.scale.max(1);
+    let (mut width, mut height) = image
+        .width()
+        .checked_mul(scale)
+        .zip(image.height().checked_mul(scale))
+        .ok_or_else(|| {
+            CliError::Usage(format!(
+                "the image can't be enlarged {} times, it would be too big",
+                scale
+            ))
+        })?;
+    if protocol == Protocol::Blocks {
+        if let Some((terminal_size::Width(columns), _)) = terminal_size::terminal_size() {
+            if width > columns as u32 {
+                height = (height as u64 * columns as u64 / width as u64).max(1) as u32;
+                width = columns as u32;
+            };
+        };
+    };
+    if (width, height) != image.dimensions() {
+        image = image::imageops::resize(&image, width, height, FilterType::Nearest);
+    };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    preview::render(&image, protocol, &mut out)
+        .and_then(|()| out.flush())
+        .for_file(Path::new("-"))
+}
+
+/// print `data` as hexadecimal, 16 bytes per line, prefixed by their offset
+fn hexdump(data: &[u8], start_offset: usize) {
+    for (index, line) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|byte| format!("{:02x}", byte)).collect();
+        println!("{:08x}  {}", start_offset + index * 16, hex.join(" "));
+    }
+}
+
+fn header(param: HeaderParameter) -> Result<(), CliError> {
+    let data = fs::read(&param.input).for_file(&param.input)?;
+    if data.len() < 28 {
+        return Err(CliError::BadCte(
+            param.input.clone(),
+            CteDecodeError::UnexpectedEnd(data.len() as u64),
+        ));
+    };
+    let field = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    let format = CteFormat::from_id(field(4));
+    let pixel_start_offset = field(24);
+
+    let magic_note = if data[0..4] == *b"\0cte" {
+        String::new()
+    } else {
+        "<- unexpected, should be 00 63 74 65".to_string()
+    };
+    let format_note = match format {
+        Some(format) => format!("({:?})", format),
+        None => "<- unknown format".to_string(),
+    };
+    let dimension_note = |value: u32| {
+        if value == 0 || !value.is_multiple_of(8) {
+            "<- unexpected, should be a non-zero multiple of 8"
+        } else {
+            ""
+        }
+    };
+    let pixel_length_note = match format {
+        Some(format) if !format.check_pixel_lenght_bit(field(16)) => format!(
+            "<- unexpected, should be {} for this format",
+            format.get_pixel_length_bit()
+        ),
+        _ => String::new(),
+    };
+    let start_note = if pixel_start_offset < 28 {
+        "<- unexpected, overlap the header"
+    } else if pixel_start_offset as usize > data.len() {
+        "<- unexpected, after the end of the file"
+    } else {
+        ""
+    };
+
+    let bytes = |offset: usize| {
+        let hex: Vec<String> = data[offset..offset + 4]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        hex.join(" ")
+    };
+    let print_line = |offset: usize, description: String, note: &str| {
+        let separator = if note.is_empty() { "" } else { " " };
+        println!(
+            "{:08x}  {}  {}{}{}",
+            offset,
+            bytes(offset),
+            description,
+            separator,
+            note
+        );
+    };
+    let fields = [
+        (4, "format id", format_note),
+        (8, "width", dimension_note(field(8)).to_string()),
+        (12, "height", dimension_note(field(12)).to_string()),
+        (16, "bits per pixel", pixel_length_note),
+        (20, "unknown", String::new()),
+        (24, "pixel data offset", start_note.to_string()),
+    ];
+    let padding_end = (pixel_start_offset as usize).clamp(28, data.len());
+    let padding = &data[28..padding_end];
+    if is_json() {
+        let note_json = |note: &str| {
+            Some(note.trim_start_matches("<- ").to_string()).filter(|note| !note.is_empty())
+        };
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|(offset, name, note)| {
+                serde_json::json!({
+                    "offset": offset,
+                    "name": name,
+                    "value": field(*offset),
+                    "note": note_json(note),
+                })
+            })
+            .collect();
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "magic": bytes(0),
+            "magic_note": note_json(&magic_note),
+            "fields": fields,
+            "padding_length": padding.len(),
+            "padding_non_zero": padding.iter().any(|byte| *byte != 0),
+        }));
+        return Ok(());
+    };
+    print_line(0, "magic".to_string(), &magic_note);
+    for (offset, name, note) in fields.iter() {
+        print_line(*offset, format!("{}: {}", name, field(*offset)), note);
+    }
+
+    if !padding.is_empty() {
+        println!("padding ({} bytes):", padding.len());
+        hexdump(padding, 28);
+        if padding.iter().any(|byte| *byte != 0) {
+            println!("the padding contain non-zero bytes");
+        };
+    };
+    Ok(())
+}
+
+/// print an histogram of the values, grouped in 16 ranges
+fn print_histogram(name: &str, histogram: &[u64; 256]) {
+    let distinct = histogram.iter().filter(|count| **count != 0).count();
+    let nibble_only = histogram
+        .iter()
+        .enumerate()
+        .all(|(value, count)| *count == 0 || value % 17 == 0);
     println!(
-        "encoding {:?} into {:?} (using the A8 encoding)",
-        param.input, param.output
+        "{} ({} distinct values{}):",
+        name,
+        distinct,
+        if nibble_only {
+            ", all representable with 4 bits"
+        } else {
+            ""
+        }
     );
-    let cte_image = CteImage {
-        original_format: CteFormat::A8,
-        image: ImageReader::open(&param.input).unwrap().decode().unwrap(),
+    let ranges: Vec<u64> = histogram
+        .chunks(16)
+        .map(|chunk| chunk.iter().sum())
+        .collect();
+    let max = ranges.iter().copied().max().unwrap_or(0).max(1);
+    for (index, count) in ranges.iter().enumerate() {
+        let line = format!(
+            "  {:3}-{:3} {:8} {}",
+            index * 16,
+            index * 16 + 15,
+            count,
+            "#".repeat((count * 40).div_ceil(max) as usize)
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+fn stats(param: StatsParameter) -> Result<(), CliError> {
+    let image = load_image(&param.input)?.into_rgba8();
+    let mut luma_histogram = [0u64; 256];
+    let mut alpha_histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        luma_histogram[LumaConversion::Average.convert(pixel.0) as usize] += 1;
+        alpha_histogram[pixel.0[3] as usize] += 1;
+    }
+    let (mut tiles, mut transparent_tiles, mut opaque_tiles) = (0, 0, 0);
+    for tile_y in (0..image.height()).step_by(8) {
+        for tile_x in (0..image.width()).step_by(8) {
+            let tile = image::imageops::crop_imm(&image, tile_x, tile_y, 8, 8);
+            let alphas: Vec<u8> = tile.pixels().map(|(_, _, pixel)| pixel.0[3]).collect();
+            tiles += 1;
+            if alphas.iter().all(|alpha| *alpha == 0) {
+                transparent_tiles += 1;
+            } else if alphas.iter().all(|alpha| *alpha == 255) {
+                opaque_tiles += 1;
+            };
+        }
+    }
+    let pixels = (image.width() as u64 * image.height() as u64).max(1);
+    let visible = pixels - alpha_histogram[0];
+    let percent = |count: u64| count as f64 * 100.0 / pixels as f64;
+    if is_json() {
+        emit(serde_json::json!({
+            "input": path_json(&param.input),
+            "width": image.width(),
+            "height": image.height(),
+            "luma_histogram": luma_histogram.to_vec(),
+            "alpha_histogram": alpha_histogram.to_vec(),
+            "tiles": tiles,
+            "transparent_tiles": transparent_tiles,
+            "opaque_tiles": opaque_tiles,
+            "visible_pixels": visible,
+            "opaque_pixels": alpha_histogram[255],
+        }));
+        return Ok(());
     };
-    let mut output = File::create(&param.output).unwrap();
-    cte_image.encode_cte(&mut output).unwrap();
-    println!("done");
+    println!("dimensions: {}x{}", image.width(), image.height());
+    print_histogram("luminance", &luma_histogram);
+    print_histogram("alpha", &alpha_histogram);
+    println!(
+        "tiles: {}, {} fully transparent, {} fully opaque",
+        tiles, transparent_tiles, opaque_tiles
+    );
+    println!(
+        "coverage: {:.1}% of the pixels are visible, {:.1}% are fully opaque",
+        percent(visible),
+        percent(alpha_histogram[255])
+    );
+    Ok(())
+}
+
+/// the hash of the dimensions and of the pixels, converted to rgba
+fn hash_file(path: &Path) -> Result<String, CliError> {
+    let data = fs::read(path).for_file(path)?;
+    let image = decode_cte(path, &mut data.as_slice())?.image.into_rgba8();
+    let mut hasher = Sha256::new();
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.as_raw());
+    let hex: Vec<String> = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    Ok(hex.concat())
+}
+
+fn hash(param: HashParameter) -> Result<(), CliError> {
+    let mut failed = 0;
+    for (input, _) in expand_patterns(&param.paths)? {
+        match hash_file(&input) {
+            Ok(hash) if is_json() => emit(serde_json::json!({
+                "input": path_json(&input),
+                "hash": hash,
+            })),
+            Ok(hash) => println!("{}  {}", hash, input.display()),
+            Err(err) => {
+                report_error(&err);
+                failed += 1;
+            }
+        };
+    }
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(CliError::BatchFailed(failed))
+    }
+}
+
+fn watch(param: WatchParameter) -> Result<(), CliError> {
+    let format_name = param.format.as_deref();
+    let default_format = param.default_format.as_deref().unwrap_or("a8");
+    let options = param.quantization.encode_options();
+    let pattern = glob_pattern("*.png")?;
+    // the paths of the events are absolute
+    let watched_dir = fs::canonicalize(&param.input).for_file(&param.input)?;
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::watcher(sender, Duration::from_millis(500))
+        .map_err(|err| CliError::Watch(param.input.clone(), err))?;
+    watcher
+        .watch(&watched_dir, RecursiveMode::Recursive)
+        .map_err(|err| CliError::Watch(param.input.clone(), err))?;
+    info!(
+        "watching {:?} for changed png images, press Ctrl+C to stop",
+        param.input
+    );
+    for event in receiver {
+        let path = match event {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Rename(_, path) => path,
+            DebouncedEvent::Error(err, path) => {
+                report_error(&CliError::Watch(
+                    path.unwrap_or_else(|| watched_dir.clone()),
+                    err,
+                ));
+                continue;
+            }
+            _ => continue,
+        };
+        let is_png = path
+            .file_name()
+            .map(|name| pattern.matches(&name.to_string_lossy()))
+            .unwrap_or(false);
+        let relative = match path.strip_prefix(&watched_dir) {
+            Ok(relative) if is_png && path.is_file() => relative,
+            _ => continue,
+        };
+        let input = param.input.join(relative);
+        let output = param.output.join(relative).with_extension("img");
+        info!("encoding {:?} to {:?}", input, output);
+        let format = (format_name, default_format);
+        let result = encode_file(&input, &output, format, None, None, &options);
+        if is_json() {
+            emit(file_result_json(&input, &output, Some(&result)));
+        } else if let Err(err) = &result {
+            report_error(err);
+        };
+    }
+    Ok(())
 }
@@ -0,0 +1,62 @@
+use crate::error::CliError;
+use serde_json::{json, Value};
+use std::{
+    error::Error,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// print the results as json instead of text, with `--json`
+pub fn set_json(enabled: bool) {
+    JSON.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// print a json object on its own line of the standard output
+pub fn emit(value: Value) {
+    println!("{}", value);
+}
+
+pub fn path_json(path: &Path) -> Value {
+    Value::String(path.display().to_string())
+}
+
+/// the error, what caused it, and how to fix it
+pub fn error_json(err: &CliError) -> Value {
+    let mut causes = Vec::new();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        causes.push(cause.to_string());
+        source = cause.source();
+    }
+    json!({
+        "message": err.to_string(),
+        "causes": causes,
+        "hint": err.hint(),
+        "exit_code": err.exit_code(),
+    })
+}
+
+/// the result of processing a file. `result` is `None` if it was skipped.
+pub fn file_result_json(
+    input: &Path,
+    output: &Path,
+    result: Option<&Result<(), CliError>>,
+) -> Value {
+    let (status, error) = match result {
+        None => ("skipped", Value::Null),
+        Some(Ok(())) => ("ok", Value::Null),
+        Some(Err(err)) => ("failed", error_json(err)),
+    };
+    json!({
+        "input": path_json(input),
+        "output": path_json(output),
+        "status": status,
+        "error": error,
+    })
+}
@@ -0,0 +1,150 @@
+use clap::ArgEnum;
+use image::{Rgb, RgbImage, RgbaImage};
+use std::{
+    collections::BTreeSet,
+    env,
+    io::{self, Write},
+};
+
+/// How an image is drawn in the terminal
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Protocol {
+    /// kitty if the terminal support it, then sixel, and otherwise blocks
+    Auto,
+    /// colored half blocks, drawing two pixels per character
+    Blocks,
+    Sixel,
+    Kitty,
+}
+
+impl Protocol {
+    /// the protocol supported by the terminal, guessed from the environment variables
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        if env::var_os("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" {
+            Self::Kitty
+        } else if term.contains("sixel") || term == "mlterm" || term.starts_with("foot") {
+            Self::Sixel
+        } else {
+            Self::Blocks
+        }
+    }
+}
+
+/// draw the image with `protocol`, which shouldn't be [`Protocol::Auto`]
+pub fn render(image: &RgbaImage, protocol: Protocol, out: &mut impl Write) -> io::Result<()> {
+    match protocol {
+        Protocol::Kitty => write_kitty(image, out),
+        Protocol::Sixel => write_sixel(&flatten(image), out),
+        Protocol::Auto | Protocol::Blocks => write_blocks(&flatten(image), out),
+    }
+}
+
+/// blend the image over a checkerboard, so transparent pixels can be told apart
+fn flatten(image: &RgbaImage) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let background = if (x / 8 + y / 8) % 2 == 0 { 64 } else { 96 };
+        let blend = |value: u8| {
+            ((value as u32 * a as u32 + background * (255 - a as u32) + 127) / 255) as u8
+        };
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+fn write_blocks(image: &RgbImage, out: &mut impl Write) -> io::Result<()> {
+    for y in (0..image.height()).step_by(2) {
+        for x in 0..image.width() {
+            let [r, g, b] = image.get_pixel(x, y).0;
+            write!(out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+            if y + 1 < image.height() {
+                let [r, g, b] = image.get_pixel(x, y + 1).0;
+                write!(out, "\x1b[48;2;{};{};{}m▀", r, g, b)?;
+            } else {
+                write!(out, "\x1b[49m▀")?;
+            };
+        }
+        writeln!(out, "\x1b[0m")?;
+    }
+    Ok(())
+}
+
+fn write_kitty(image: &RgbaImage, out: &mut impl Write) -> io::Result<()> {
+    let data = base64::encode(image.as_raw());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = if index + 1 < chunks.len() { 1 } else { 0 };
+        if index == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},m={};",
+                image.width(),
+                image.height(),
+                more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        };
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    writeln!(out)
+}
+
+/// write the image as sixel, using a palette of 6 levels for each channel
+fn write_sixel(image: &RgbImage, out: &mut impl Write) -> io::Result<()> {
+    let level = |value: u8| (value as u32 * 5 + 127) / 255;
+    let color_index = |x: u32, y: u32| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        level(r) * 36 + level(g) * 6 + level(b)
+    };
+    write!(out, "\x1bPq\"1;1;{};{}", image.width(), image.height())?;
+    for index in 0..216 {
+        let (r, g, b) = (index / 36, index / 6 % 6, index % 6);
+        write!(out, "#{};2;{};{};{}", index, r * 20, g * 20, b * 20)?;
+    }
+    for band in (0..image.height()).step_by(6) {
+        let band_height = (image.height() - band).min(6);
+        let colors: BTreeSet<u32> = (0..band_height)
+            .flat_map(|dy| (0..image.width()).map(move |x| (x, band + dy)))
+            .map(|(x, y)| color_index(x, y))
+            .collect();
+        for (position, color) in colors.iter().enumerate() {
+            if position != 0 {
+                write!(out, "$")?;
+            };
+            write!(out, "#{}", color)?;
+            // each character is a column of 6 pixels, run-length encoded
+            let mut run: Option<(u8, u32)> = None;
+            for x in 0..image.width() {
+                let bits = (0..band_height)
+                    .filter(|dy| color_index(x, band + dy) == *color)
+                    .fold(0, |bits, dy| bits | 1 << dy);
+                let character = 63 + bits as u8;
+                run = match run {
+                    Some((previous, count)) if previous == character => {
+                        Some((character, count + 1))
+                    }
+                    Some(previous) => {
+                        write_sixel_run(previous, out)?;
+                        Some((character, 1))
+                    }
+                    None => Some((character, 1)),
+                };
+            }
+            if let Some(run) = run {
+                write_sixel_run(run, out)?;
+            };
+        }
+        write!(out, "-")?;
+    }
+    writeln!(out, "\x1b\\")
+}
+
+fn write_sixel_run((character, count): (u8, u32), out: &mut impl Write) -> io::Result<()> {
+    if count > 3 {
+        write!(out, "!{}{}", count, character as char)
+    } else {
+        (0..count).try_for_each(|_| write!(out, "{}", character as char))
+    }
+}
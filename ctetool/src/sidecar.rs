@@ -0,0 +1,81 @@
+use crate::error::{CliError, ResultExt};
+use image::{DynamicImage, GenericImageView};
+use log::warn;
+use pmd_cte::{CteFormat, CteImage, EncodeOptions};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The header settings of an extracted cte file, stored in a `.cte.toml` file next to the
+/// extracted image so it can be encoded back identically
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sidecar {
+    pub format: String,
+    pub unk: u32,
+    pub pixel_start_offset: u32,
+    /// the dimensions of the image, which may be smaller than the stored one
+    pub logical_size: [u32; 2],
+}
+
+impl Sidecar {
+    /// the sidecar file of the extracted image at `path`
+    pub fn path_for(path: &Path) -> PathBuf {
+        path.with_extension("cte.toml")
+    }
+
+    pub fn from_image(image: &CteImage) -> Self {
+        let (width, height) = image.logical_dimensions();
+        Self {
+            format: image.original_format.name().unwrap_or_default().to_string(),
+            unk: image.header.as_ref().map(|header| header.unk).unwrap_or(0),
+            pixel_start_offset: image
+                .header
+                .as_ref()
+                .map(|header| header.pixel_start_offset)
+                .unwrap_or_else(|| EncodeOptions::default().pixel_start_offset),
+            logical_size: [width, height],
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), CliError> {
+        let content = toml::to_string(self).expect("the sidecar should be serializable");
+        fs::write(path, content).for_file(path)
+    }
+
+    /// read the sidecar of the image at `image_path`, if there is one
+    pub fn read_for(image_path: &Path) -> Result<Option<Self>, CliError> {
+        let path = Self::path_for(image_path);
+        if !path.is_file() {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&path).for_file(&path)?;
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(|err| CliError::Sidecar(path, err))
+    }
+
+    /// apply the settings of the sidecar to the encoding of `image`, returning the format to
+    /// encode to
+    pub fn apply(
+        &self,
+        image: &DynamicImage,
+        options: &mut EncodeOptions,
+    ) -> Result<CteFormat, CliError> {
+        if image.dimensions() != (self.logical_size[0], self.logical_size[1]) {
+            warn!(
+                "the image is {}x{}, while it was {}x{} when extracted",
+                image.width(),
+                image.height(),
+                self.logical_size[0],
+                self.logical_size[1]
+            );
+        };
+        options.unk = Some(self.unk);
+        options.pixel_start_offset = self.pixel_start_offset;
+        options.pad = true;
+        Ok(self.format.parse()?)
+    }
+}
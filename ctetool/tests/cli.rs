@@ -0,0 +1,299 @@
+//! Run the ctetool binary on files written to a temporary directory
+
+use image::{Rgba, RgbaImage};
+use serde_json::Value;
+use std::convert::TryInto;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+/// A directory removed at the end of the test
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ctetool-test-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// run ctetool in `dir`, ignoring the configuration files of the user
+fn ctetool(dir: &TempDir, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ctetool"))
+        .args(args)
+        .current_dir(&dir.0)
+        .env("HOME", &dir.0)
+        .env("XDG_CONFIG_HOME", &dir.0)
+        .output()
+        .unwrap()
+}
+
+/// the json objects printed on the standard output
+fn json_lines(output: &Output) -> Vec<Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// a 16×16 gray image, with transparent pixels on the left
+fn write_picture(path: &Path) -> RgbaImage {
+    let image = RgbaImage::from_fn(16, 16, |x, y| {
+        let value = (x * 16 + y) as u8;
+        Rgba([value, value, value, if x < 8 { 0x44 } else { 255 }])
+    });
+    image.save(path).unwrap();
+    image
+}
+
+/// encode a picture to `name` in the given format
+fn encode(dir: &TempDir, name: &str, format: &str) -> RgbaImage {
+    let image = write_picture(&dir.path("source.png"));
+    let output = ctetool(dir, &["encode", "source.png", name, "--format", format]);
+    assert!(output.status.success(), "{:?}", output);
+    image
+}
+
+#[test]
+fn inspect_json() {
+    let dir = TempDir::new("inspect");
+    encode(&dir, "font.img", "la8");
+    let output = ctetool(&dir, &["--json", "inspect", "font.img"]);
+    assert!(output.status.success(), "{:?}", output);
+    let lines = json_lines(&output);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["format"], "La8");
+    assert_eq!(lines[0]["format_id"], 5);
+    assert_eq!(lines[0]["bits_per_pixel"], 16);
+    assert_eq!(
+        (&lines[0]["width"], &lines[0]["height"]),
+        (&16.into(), &16.into())
+    );
+    assert_eq!(
+        lines[0]["file_size"],
+        fs::metadata(dir.path("font.img")).unwrap().len()
+    );
+}
+
+#[test]
+fn encode_extract_round_trip() {
+    let dir = TempDir::new("round-trip");
+    let image = encode(&dir, "image.img", "rgba8888");
+    let output = ctetool(&dir, &["extract", "image.img", "extracted.png"]);
+    assert!(output.status.success(), "{:?}", output);
+    let extracted = image::open(dir.path("extracted.png")).unwrap().to_rgba8();
+    assert_eq!(extracted, image);
+}
+
+#[test]
+fn verify() {
+    let dir = TempDir::new("verify");
+    encode(&dir, "image.img", "la4");
+    let output = ctetool(&dir, &["--json", "verify", "image.img"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(json_lines(&output).len(), 1);
+}
+
+#[test]
+fn convert() {
+    let dir = TempDir::new("convert");
+    encode(&dir, "image.img", "la8");
+    let output = ctetool(
+        &dir,
+        &["convert", "image.img", "converted.img", "--format", "a8"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let output = ctetool(&dir, &["--json", "inspect", "converted.img"]);
+    assert_eq!(json_lines(&output)[0]["format"], "A8");
+}
+
+#[test]
+fn exit_codes() {
+    let dir = TempDir::new("exit-codes");
+    encode(&dir, "image.img", "a8");
+    write_picture(&dir.path("picture.png"));
+    let mut unsupported = fs::read(dir.path("image.img")).unwrap();
+    unsupported[4] = 99;
+    fs::write(dir.path("unsupported.img"), unsupported).unwrap();
+
+    for (args, code) in [
+        (
+            &["convert", "image.img", "image.img", "--format", "la8"][..],
+            1,
+        ),
+        (&["convert", "image.img", "out.img", "--format", "nope"], 2),
+        (&["extract", "picture.png", "out.png"], 3),
+        (&["extract", "unsupported.img", "out.png"], 4),
+        (&["inspect", "missing.img"], 5),
+    ] {
+        let output = ctetool(&dir, args);
+        assert_eq!(output.status.code(), Some(code), "{:?}: {:?}", args, output);
+
+        let output = ctetool(&dir, &[&["--json"], args].concat());
+        assert_eq!(output.status.code(), Some(code), "{:?}: {:?}", args, output);
+        let lines = json_lines(&output);
+        let error = &lines.last().unwrap()["error"];
+        assert_eq!(error["exit_code"], code, "{:?}", args);
+    }
+}
+
+#[test]
+fn encode_format_names() {
+    let dir = TempDir::new("format-names");
+    for (name, stored) in [
+        ("rgba4444", "Rgba4444"),
+        ("LA4", "La4"),
+        ("rgba5551", "Rgba5551"),
+    ] {
+        encode(&dir, "image.img", name);
+        let output = ctetool(&dir, &["--json", "inspect", "image.img"]);
+        assert_eq!(json_lines(&output)[0]["format"], stored, "{}", name);
+        fs::remove_file(dir.path("image.img")).unwrap();
+    }
+}
+
+#[test]
+fn verify_mipmaps_and_lenient_offset() {
+    let dir = TempDir::new("verify-mipmaps");
+    encode(&dir, "image.img", "a8");
+    let mut data = fs::read(dir.path("image.img")).unwrap();
+    // a 8×8 mipmap level after the 16×16 image
+    data.extend((0..64).map(|value| value * 3));
+    fs::write(dir.path("mipmaps.img"), &data).unwrap();
+    let output = ctetool(&dir, &["--json", "verify", "mipmaps.img"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(json_lines(&output)[0]["mipmaps"], 1);
+
+    // the pixel data start inside the header, which is only accepted in lenient mode
+    let pixel_start = u32::from_le_bytes(data[24..28].try_into().unwrap()) as usize;
+    data.drain(28..pixel_start);
+    data[24..28].copy_from_slice(&12u32.to_le_bytes());
+    fs::write(dir.path("lenient.img"), &data).unwrap();
+    let output = ctetool(&dir, &["--json", "--lenient", "verify", "lenient.img"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(json_lines(&output).last().unwrap()["max_error"], 0);
+}
+
+#[test]
+fn convert_to_rgba4444() {
+    let dir = TempDir::new("convert-rgba4444");
+    encode(&dir, "image.img", "la4");
+    let output = ctetool(
+        &dir,
+        &[
+            "convert",
+            "image.img",
+            "converted.img",
+            "--format",
+            "rgba4444",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let output = ctetool(&dir, &["--json", "inspect", "converted.img"]);
+    let header = &json_lines(&output)[0];
+    assert_eq!(header["format"], "Rgba4444");
+    assert_eq!(header["bits_per_pixel"], 16);
+
+    // LA4 values can be stored exactly in RGBA4444
+    let output = ctetool(&dir, &["--json", "diff", "image.img", "converted.img"]);
+    assert_eq!(json_lines(&output)[0]["differing_pixels"], 0);
+}
+
+#[test]
+fn build_atlas_rejects_glyphs_outside_the_grid() {
+    let dir = TempDir::new("atlas");
+    fs::create_dir(dir.path("glyphs")).unwrap();
+    RgbaImage::new(8, 8)
+        .save(dir.path("glyphs/glyph_0.png"))
+        .unwrap();
+    let build = |columns: &str| {
+        ctetool(
+            &dir,
+            &[
+                "build-atlas",
+                "glyphs",
+                "font.img",
+                "--cell",
+                "8x8",
+                "--columns",
+                columns,
+            ],
+        )
+    };
+    let output = build("2");
+    assert!(output.status.success(), "{:?}", output);
+
+    for index in ["4294967295", "600000000"] {
+        let _ = fs::remove_file(dir.path("font.img"));
+        let glyph = dir.path(&format!("glyphs/glyph_{}.png", index));
+        RgbaImage::new(8, 8).save(&glyph).unwrap();
+        let output = build("1");
+        assert_eq!(output.status.code(), Some(3), "{}: {:?}", index, output);
+        fs::remove_file(glyph).unwrap();
+    }
+
+    let output = build("4294967295");
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
+
+#[test]
+fn preview_rejects_too_big_scale() {
+    let dir = TempDir::new("preview");
+    encode(&dir, "image.img", "a8");
+    let output = ctetool(
+        &dir,
+        &[
+            "preview",
+            "image.img",
+            "--protocol",
+            "blocks",
+            "--scale",
+            "4294967295",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
+
+#[test]
+fn format_flag_take_precedence_over_sidecar() {
+    let dir = TempDir::new("sidecar");
+    encode(&dir, "original.img", "la8");
+    let output = ctetool(
+        &dir,
+        &["extract", "original.img", "extracted.png", "--sidecar"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.path("extracted.cte.toml").is_file());
+
+    let output = ctetool(&dir, &["encode", "extracted.png", "sidecar.img"]);
+    assert!(output.status.success(), "{:?}", output);
+    let output = ctetool(&dir, &["--json", "inspect", "sidecar.img"]);
+    assert_eq!(json_lines(&output)[0]["format"], "La8");
+
+    let output = ctetool(
+        &dir,
+        &[
+            "encode",
+            "extracted.png",
+            "flag.img",
+            "--format",
+            "rgba4444",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("as asked with --format"));
+    let output = ctetool(&dir, &["--json", "inspect", "flag.img"]);
+    assert_eq!(json_lines(&output)[0]["format"], "Rgba4444");
+}
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pmd_cte::{decode_rgba8, CteImage, CteView, DecodeOptions};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CteImage::decode_cte(&mut Cursor::new(data));
+    let _ = CteImage::validate(&mut Cursor::new(data));
+    let _ = decode_rgba8(data, &DecodeOptions::default());
+    if let Ok(view) = CteView::new(data) {
+        let _ = view.get_pixel(0, 0);
+    };
+});
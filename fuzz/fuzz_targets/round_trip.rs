@@ -0,0 +1,44 @@
+#![no_main]
+use image::{DynamicImage, RgbaImage};
+use libfuzzer_sys::fuzz_target;
+use pmd_cte::{CteFormat, CteImage, EncodeOptions};
+use std::io::Cursor;
+
+// the first three bytes choose the format and the number of tiles of the image, and the other
+// ones are its pixels, repeated to fill it
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 4 {
+        return;
+    };
+    let format = CteFormat::BUILTIN[data[0] as usize % CteFormat::BUILTIN.len()];
+    let width = (data[1] as u32 % 4 + 1) * 8;
+    let height = (data[2] as u32 % 4 + 1) * 8;
+    let pixels = data[3..]
+        .iter()
+        .copied()
+        .cycle()
+        .take((width * height * 4) as usize)
+        .collect();
+    let image = CteImage::new(
+        format,
+        DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, pixels).unwrap()),
+    );
+
+    let mut encoded = Vec::new();
+    image
+        .encode_cte_with(&mut encoded, &EncodeOptions::default())
+        .unwrap();
+    let decoded = CteImage::decode_cte(&mut Cursor::new(&encoded)).unwrap();
+    assert_eq!(decoded.original_format, format);
+    assert_eq!(decoded.image.to_rgba8().dimensions(), (width, height));
+
+    // the decoded pixels can be represented exactly, so encoding them again shouldn't change
+    // anything, except with ETC1 whose compression is lossy
+    if !matches!(format, CteFormat::Etc1 | CteFormat::Etc1A4) {
+        let mut encoded_again = Vec::new();
+        decoded
+            .encode_cte_with(&mut encoded_again, &EncodeOptions::default())
+            .unwrap();
+        assert_eq!(encoded, encoded_again);
+    };
+});
@@ -0,0 +1,64 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::{DynamicImage, Rgba, RgbaImage};
+use pmd_cte::{CteFormat, CteImage};
+
+/// the width and height of the benchmarked images
+const SIZES: [u32; 3] = [64, 256, 1024];
+
+/// a synthetic image with varying color and alpha, so no format get a trivial input
+fn fixture(size: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(size, size, |x, y| {
+        Rgba([
+            (x * 255 / size) as u8,
+            (y * 255 / size) as u8,
+            ((x ^ y) & 0xFF) as u8,
+            ((x + y) * 255 / (size * 2)) as u8,
+        ])
+    }))
+}
+
+fn encoded_fixture(format: CteFormat, size: u32) -> Vec<u8> {
+    CteImage::new(format, fixture(size))
+        .encode_to_vec()
+        .expect("the fixture should be encodable")
+}
+
+fn format_name(format: CteFormat) -> &'static str {
+    format.name().expect("the built-in formats have a name")
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for format in CteFormat::BUILTIN.iter() {
+        for size in SIZES.iter() {
+            let data = encoded_fixture(*format, *size);
+            group.throughput(Throughput::Elements(*size as u64 * *size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format_name(*format), size),
+                &data,
+                |b, data| b.iter(|| CteImage::decode_from_slice(data).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    group.sample_size(10);
+    for format in CteFormat::BUILTIN.iter() {
+        for size in SIZES.iter() {
+            let image = CteImage::new(*format, fixture(*size));
+            group.throughput(Throughput::Elements(*size as u64 * *size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format_name(*format), size),
+                &image,
+                |b, image| b.iter(|| image.encode_to_vec().unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode, encode);
+criterion_main!(benches);
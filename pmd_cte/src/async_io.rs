@@ -0,0 +1,44 @@
+use crate::{CteDecodeError, CteEncodeError, CteImage, DecodeOptions, EncodeOptions};
+use std::io::Cursor;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The whole file is read (or encoded) in memory, then decoded (or written). Only the IO is
+/// asynchronous.
+impl CteImage {
+    pub async fn decode_cte_async<R: AsyncRead + Unpin>(
+        input: &mut R,
+    ) -> Result<CteImage, CteDecodeError> {
+        Self::decode_cte_async_with(input, &DecodeOptions::default()).await
+    }
+
+    pub async fn decode_cte_async_with<R: AsyncRead + Unpin>(
+        input: &mut R,
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteDecodeError> {
+        let mut data = Vec::new();
+        if let Err(err) = input.read_to_end(&mut data).await {
+            return Err(CteDecodeError::IOError(err, data.len() as u64));
+        };
+        Self::decode_cte_with(&mut Cursor::new(data), options)
+    }
+
+    pub async fn encode_cte_async<W: AsyncWrite + Unpin>(
+        &self,
+        out: &mut W,
+    ) -> Result<(), CteEncodeError> {
+        self.encode_cte_async_with(out, &EncodeOptions::default())
+            .await
+    }
+
+    pub async fn encode_cte_async_with<W: AsyncWrite + Unpin>(
+        &self,
+        out: &mut W,
+        options: &EncodeOptions,
+    ) -> Result<(), CteEncodeError> {
+        let mut data = Vec::new();
+        self.encode_cte_with(&mut data, options)?;
+        out.write_all(&data).await?;
+        out.flush().await?;
+        Ok(())
+    }
+}
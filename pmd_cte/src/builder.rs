@@ -0,0 +1,57 @@
+use crate::{CteEncodeError, CteFormat, CteImage};
+use image::{DynamicImage, GenericImageView};
+
+/// Build a [`CteImage`], checking it can be encoded in the chosen format
+pub struct CteImageBuilder {
+    image: DynamicImage,
+    format: CteFormat,
+    allow_alpha_discard: bool,
+    allow_padding: bool,
+}
+
+impl CteImageBuilder {
+    pub fn new(image: DynamicImage, format: CteFormat) -> Self {
+        Self {
+            image,
+            format,
+            allow_alpha_discard: false,
+            allow_padding: false,
+        }
+    }
+
+    /// the format the image will be encoded to
+    pub fn format(mut self, format: CteFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// accept transparent image for a format without alpha channel, which will then be discarded
+    pub fn allow_alpha_discard(mut self, allow: bool) -> Self {
+        self.allow_alpha_discard = allow;
+        self
+    }
+
+    /// accept image whose dimensions aren't a multiple of 8. They should then be encoded with
+    /// [`crate::EncodeOptions::pad`] enabled.
+    pub fn allow_padding(mut self, allow: bool) -> Self {
+        self.allow_padding = allow;
+        self
+    }
+
+    pub fn build(self) -> Result<CteImage, CteEncodeError> {
+        if !self.allow_padding && !self.image.width().is_multiple_of(8) {
+            return Err(CteEncodeError::WidthNotMultiple8(self.image.width()));
+        };
+        if !self.allow_padding && !self.image.height().is_multiple_of(8) {
+            return Err(CteEncodeError::HeightNotMultiple8(self.image.height()));
+        };
+        if !self.allow_alpha_discard
+            && !self.format.has_alpha()
+            && self.image.color().has_alpha()
+            && self.image.pixels().any(|(_, _, pixel)| pixel.0[3] != 255)
+        {
+            return Err(CteEncodeError::AlphaDiscarded(self.format));
+        };
+        Ok(CteImage::new(self.format, self.image))
+    }
+}
@@ -0,0 +1,269 @@
+//! Conversion from and to the BCLIM and BFLIM texture containers of the 3DS. They store the
+//! pixel data with the same tiling as cte files, but with dimensions padded to powers of two, and
+//! followed by a footer describing it.
+
+use crate::reader::PositionTracker;
+use crate::{decode_level, encode_level, CteDecodeError, CteEncodeError, CteFormat, CteHeader};
+use crate::{level_byte_size, CteImage, DecodeOptions, EncodeOptions, CTE_HEADER_SIZE};
+use image::GenericImageView;
+use log::warn;
+use std::io::Cursor;
+
+/// the size of the footer, made of the file header, the `imag` block and the size of the data
+const CLIM_FOOTER_SIZE: usize = 0x28;
+/// the biggest dimension of a texture on the 3DS
+const MAX_TEXTURE_DIMENSION: u32 = 1024;
+
+/// A 3DS texture container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClimContainer {
+    Bclim,
+    Bflim,
+}
+
+impl ClimContainer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bclim => "BCLIM",
+            Self::Bflim => "BFLIM",
+        }
+    }
+
+    fn magic(self) -> [u8; 4] {
+        match self {
+            Self::Bclim => *b"CLIM",
+            Self::Bflim => *b"FLIM",
+        }
+    }
+
+    fn version(self) -> u32 {
+        match self {
+            Self::Bclim => 0x0202_0000,
+            Self::Bflim => 0x0702_0000,
+        }
+    }
+}
+
+/// the id of the format in the `imag` block, if the container support it
+fn clim_format_id(format: CteFormat) -> Option<u8> {
+    Some(match format {
+        CteFormat::L8 => 0,
+        CteFormat::A8 => 1,
+        CteFormat::La4 => 2,
+        CteFormat::La8 => 3,
+        CteFormat::Hilo8 => 4,
+        CteFormat::Rgb565 => 5,
+        CteFormat::Rgb888 => 6,
+        CteFormat::Rgba5551 => 7,
+        CteFormat::Rgba4444 => 8,
+        CteFormat::Rgba8888 => 9,
+        CteFormat::Etc1 => 10,
+        CteFormat::Etc1A4 => 11,
+        CteFormat::L4 => 12,
+        CteFormat::A4 => 13,
+        CteFormat::Custom(_) => return None,
+    })
+}
+
+/// The texture of a BCLIM or BFLIM file, as described by its footer
+struct ClimTexture<'a> {
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    /// the header of a cte file storing the whole texture, to find its tiles
+    texture_header: CteHeader,
+    data: &'a [u8],
+}
+
+impl<'a> ClimTexture<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, CteDecodeError> {
+        if data.len() < CLIM_FOOTER_SIZE {
+            return Err(CteDecodeError::UnexpectedEnd(data.len() as u64));
+        };
+        let footer_start = data.len() - CLIM_FOOTER_SIZE;
+        let footer = &data[footer_start..];
+        let magic = [footer[0], footer[1], footer[2], footer[3]];
+        let container = [ClimContainer::Bclim, ClimContainer::Bflim]
+            .iter()
+            .copied()
+            .find(|container| container.magic() == magic)
+            .ok_or(CteDecodeError::InvalideHeader(magic, footer_start as u64))?;
+        let field_u16 = |offset: usize| u16::from_le_bytes([footer[offset], footer[offset + 1]]);
+        let format_id = match container {
+            ClimContainer::Bclim => field_u16(0x20),
+            ClimContainer::Bflim => {
+                let swizzle = footer[0x23];
+                if swizzle != 0 {
+                    return Err(CteDecodeError::ClimRotated(
+                        swizzle,
+                        (footer_start + 0x23) as u64,
+                    ));
+                };
+                footer[0x22] as u16
+            }
+        };
+        let format = CteFormat::BUILTIN
+            .iter()
+            .copied()
+            .find(|format| clim_format_id(*format) == Some(format_id as u8))
+            .ok_or(CteDecodeError::UnsuportedFormat(
+                format_id as u32,
+                (footer_start + 0x20) as u64,
+            ))?;
+        let width = field_u16(0x1C) as u32;
+        let height = field_u16(0x1E) as u32;
+        let texture_header = CteHeader {
+            format_id: format.get_id(),
+            width: texture_dimension(width),
+            height: texture_dimension(height),
+            pixel_length: format.get_pixel_length_bit(),
+            unk: 0,
+            pixel_start_offset: 0,
+        };
+        let expected = level_byte_size(texture_header.width, texture_header.height, format.codec());
+        if (footer_start as u64) < expected {
+            return Err(CteDecodeError::PixelDataTooShort(
+                footer_start as u64,
+                expected,
+                footer_start as u64,
+            ));
+        };
+        Ok(Self {
+            width,
+            height,
+            format,
+            texture_header,
+            data: &data[..expected as usize],
+        })
+    }
+}
+
+/// convert a BCLIM or BFLIM file to a cte file, copying the tiles containing the image without
+/// decoding them. The stored dimensions are rounded up to multiples of 8 instead of powers of two.
+pub fn clim_to_cte(data: &[u8], options: &EncodeOptions) -> Result<Vec<u8>, CteDecodeError> {
+    let texture = ClimTexture::parse(data)?;
+    let tile_size = texture.format.get_tile_size();
+    let header = CteHeader {
+        width: texture.width.div_ceil(8) * 8,
+        height: texture.height.div_ceil(8) * 8,
+        unk: options.unk.unwrap_or(0),
+        pixel_start_offset: options.pixel_start_offset.max(CTE_HEADER_SIZE as u32),
+        ..texture.texture_header.clone()
+    };
+    let mut result = Vec::new();
+    header
+        .write_with_padding(&mut result, options.padding_fill)
+        .expect("writing to a Vec should not fail");
+    // the tiles are stored starting with the bottom row of the image
+    for y in (0..header.height).step_by(8).rev() {
+        for x in (0..header.width).step_by(8) {
+            let offset = texture
+                .texture_header
+                .tile_offset(tile_size, x, y)
+                .expect("the image should fit in the texture") as usize;
+            result.extend_from_slice(&texture.data[offset..offset + tile_size]);
+        }
+    }
+    Ok(result)
+}
+
+/// the dimension of the texture storing an image of this dimension
+fn texture_dimension(dimension: u32) -> u32 {
+    dimension.max(8).next_power_of_two()
+}
+
+impl CteImage {
+    /// decode the texture of a BCLIM or BFLIM file
+    pub fn decode_clim(data: &[u8]) -> Result<CteImage, CteDecodeError> {
+        Self::decode_clim_with(data, &DecodeOptions::default())
+    }
+
+    pub fn decode_clim_with(
+        data: &[u8],
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteDecodeError> {
+        let texture = ClimTexture::parse(data)?;
+        let image = decode_level(
+            &mut PositionTracker::new(Cursor::new(texture.data)),
+            texture.texture_header.width,
+            texture.texture_header.height,
+            texture.format,
+            options,
+        )?;
+        Ok(CteImage::new(
+            texture.format,
+            image.crop_imm(0, 0, texture.width, texture.height),
+        ))
+    }
+
+    /// encode the image in a BCLIM file, in the format it was decoded from. Mipmaps are ignored.
+    pub fn to_bclim(&self) -> Result<Vec<u8>, CteEncodeError> {
+        self.to_clim_with(ClimContainer::Bclim, &EncodeOptions::default())
+    }
+
+    /// encode the image in a BFLIM file, like [`CteImage::to_bclim`]
+    pub fn to_bflim(&self) -> Result<Vec<u8>, CteEncodeError> {
+        self.to_clim_with(ClimContainer::Bflim, &EncodeOptions::default())
+    }
+
+    /// encode the image in the given container, using `options.format` if set. The texture is
+    /// padded with transparent pixels to dimensions that are powers of two.
+    pub fn to_clim_with(
+        &self,
+        container: ClimContainer,
+        options: &EncodeOptions,
+    ) -> Result<Vec<u8>, CteEncodeError> {
+        let format = options.format.unwrap_or(self.original_format);
+        let format_id = clim_format_id(format).ok_or(
+            CteEncodeError::FormatNotSupportedByContainer(format, container.name()),
+        )?;
+        let (width, height) = self.image.dimensions();
+        for dimension in [width, height] {
+            if dimension > MAX_TEXTURE_DIMENSION {
+                return Err(CteEncodeError::DimensionTooBig(dimension));
+            };
+        }
+        let texture_dimensions = (texture_dimension(width), texture_dimension(height));
+        let mut result = Vec::new();
+        let mut alpha_discarded = false;
+        encode_level(
+            &self.image,
+            texture_dimensions,
+            format,
+            options,
+            &mut result,
+            &mut alpha_discarded,
+        )?;
+        if alpha_discarded {
+            warn!(
+                "the image contain transparent pixel, but the {:?} format doesn't store alpha. It has been discarded.",
+                format
+            );
+        };
+        let data_size = result.len() as u32;
+        let file_size = result.len() + CLIM_FOOTER_SIZE;
+
+        result.extend_from_slice(&container.magic());
+        result.extend_from_slice(&0xFEFFu16.to_le_bytes());
+        result.extend_from_slice(&0x14u16.to_le_bytes());
+        result.extend_from_slice(&container.version().to_le_bytes());
+        result.extend_from_slice(&(file_size as u32).to_le_bytes());
+        // the number of blocks, followed by padding
+        result.extend_from_slice(&1u32.to_le_bytes());
+
+        result.extend_from_slice(b"imag");
+        result.extend_from_slice(&0x10u32.to_le_bytes());
+        result.extend_from_slice(&(width as u16).to_le_bytes());
+        result.extend_from_slice(&(height as u16).to_le_bytes());
+        match container {
+            ClimContainer::Bclim => result.extend_from_slice(&(format_id as u32).to_le_bytes()),
+            ClimContainer::Bflim => {
+                // the alignment, and the swizzle mode, with the texture not rotated
+                result.extend_from_slice(&0x80u16.to_le_bytes());
+                result.extend_from_slice(&[format_id, 0]);
+            }
+        };
+        result.extend_from_slice(&data_size.to_le_bytes());
+        Ok(result)
+    }
+}
@@ -0,0 +1,603 @@
+//! The per-format pixel encoding and decoding.
+//!
+//! Each format is handled by a type implementing [`CteFormatCodec`], which convert a single 8×8
+//! tile between its stored representation and RGBA pixels. The pixels of a tile are always in the
+//! order they are stored in the file (see [`crate::CteImage`] for the tile layout).
+
+use crate::{etc1, simd, EncodeOptions};
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use image::ColorType;
+
+/// Conversion between the stored data of a tile and its pixels, for a single format.
+///
+/// This is implemented for every built-in format, and can be implemented for formats this crate
+/// doesn't know of, to then be used with [`crate::CteFormat::Custom`].
+pub trait CteFormatCodec: Debug + Send + Sync {
+    /// the id of the format, as stored in the header of the file
+    fn id(&self) -> u32;
+
+    /// the number of bit used to store a single pixel
+    fn bits_per_pixel(&self) -> u32;
+
+    /// true if the format store an alpha channel
+    fn has_alpha(&self) -> bool;
+
+    /// the color type of the decoded image. RGB or RGBA by default, so that codecs written
+    /// without the `std` feature still compile once another crate enable it.
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        if self.has_alpha() {
+            ColorType::Rgba8
+        } else {
+            ColorType::Rgb8
+        }
+    }
+
+    /// the number of byte used to store a 8×8 tile
+    fn tile_size(&self) -> usize {
+        (64 * self.bits_per_pixel() / 8) as usize
+    }
+
+    /// decode the `tile_size()` bytes of `data` into RGBA pixels
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]);
+
+    /// encode the RGBA pixels into `out`, which is `tile_size()` bytes long
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]);
+}
+
+/// expand a 5 bit value (in the lower bits) to the 0-255 range
+fn expand_5_bit(value: u8) -> u8 {
+    let value = value & 0x1F;
+    (value << 3) | (value >> 2)
+}
+
+/// expand a 6 bit value (in the lower bits) to the 0-255 range
+fn expand_6_bit(value: u8) -> u8 {
+    let value = value & 0x3F;
+    (value << 2) | (value >> 4)
+}
+
+/// reduce a 8 bit value to a value with only `bits` bits, rounding to the nearest one
+fn reduce_bit(value: u8, bits: u32) -> u8 {
+    let max = (1u32 << bits) - 1;
+    ((value as u32 * max + 127) / 255) as u8
+}
+
+/// reduce a 8 bit value to a 4 bit one, rounding to the nearest one
+pub(crate) fn quantize_4_bit(value: u8) -> u8 {
+    ((value as u32 + 8) / 17) as u8
+}
+
+/// expand a 4 bit value to the 0-255 range, so 15 become 255. `quantize_4_bit` give back the
+/// original value.
+pub(crate) fn expand_4_bit(value: u8) -> u8 {
+    value * 17
+}
+
+/// split each byte in two 4 bit values, the lower nibble being the first one
+fn unpack_nibbles(data: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    data.iter().flat_map(|v| [v & 0x0F, v >> 4])
+}
+
+/// pack two 4 bit values in a byte, the first one in the lower nibble
+fn pack_nibbles(first: u8, second: u8) -> u8 {
+    (first & 0x0F) | (second << 4)
+}
+
+/// 32 bit RGBA, stored as ABGR
+#[derive(Debug)]
+pub struct Rgba8888Codec;
+
+impl CteFormatCodec for Rgba8888Codec {
+    fn id(&self) -> u32 {
+        0
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        32
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(4)) {
+            *pixel = [chunk[3], chunk[2], chunk[1], chunk[0]];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(4)) {
+            chunk.copy_from_slice(&[pixel[3], pixel[2], pixel[1], pixel[0]]);
+        }
+    }
+}
+
+/// 24 bit RGB, stored as BGR
+#[derive(Debug)]
+pub struct Rgb888Codec;
+
+impl CteFormatCodec for Rgb888Codec {
+    fn id(&self) -> u32 {
+        1
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        24
+    }
+
+    fn has_alpha(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgb8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(3)) {
+            *pixel = [chunk[2], chunk[1], chunk[0], 255];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(3)) {
+            chunk.copy_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+    }
+}
+
+/// 16 bit RGBA, with 5 bit for each color and a single bit for alpha
+#[derive(Debug)]
+pub struct Rgba5551Codec;
+
+impl CteFormatCodec for Rgba5551Codec {
+    fn id(&self) -> u32 {
+        2
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        16
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(2)) {
+            let value = u16::from_le_bytes([chunk[0], chunk[1]]);
+            *pixel = [
+                expand_5_bit((value >> 11) as u8),
+                expand_5_bit((value >> 6) as u8),
+                expand_5_bit((value >> 1) as u8),
+                if value & 1 == 1 { 255 } else { 0 },
+            ];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(2)) {
+            let value = ((reduce_bit(pixel[0], 5) as u16) << 11)
+                | ((reduce_bit(pixel[1], 5) as u16) << 6)
+                | ((reduce_bit(pixel[2], 5) as u16) << 1)
+                | (pixel[3] >= 128) as u16;
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// 16 bit RGB, with 5 bit for red and blue, and 6 bit for green
+#[derive(Debug)]
+pub struct Rgb565Codec;
+
+impl CteFormatCodec for Rgb565Codec {
+    fn id(&self) -> u32 {
+        3
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        16
+    }
+
+    fn has_alpha(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgb8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(2)) {
+            let value = u16::from_le_bytes([chunk[0], chunk[1]]);
+            *pixel = [
+                expand_5_bit((value >> 11) as u8),
+                expand_6_bit((value >> 5) as u8),
+                expand_5_bit(value as u8),
+                255,
+            ];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(2)) {
+            let value = ((reduce_bit(pixel[0], 5) as u16) << 11)
+                | ((reduce_bit(pixel[1], 6) as u16) << 5)
+                | reduce_bit(pixel[2], 5) as u16;
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// 16 bit RGBA, with 4 bit for each channel
+#[derive(Debug)]
+pub struct Rgba4444Codec;
+
+impl CteFormatCodec for Rgba4444Codec {
+    fn id(&self) -> u32 {
+        4
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        16
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(2)) {
+            let value = u16::from_le_bytes([chunk[0], chunk[1]]);
+            *pixel = [
+                expand_4_bit((value >> 12) as u8 & 0xF),
+                expand_4_bit((value >> 8) as u8 & 0xF),
+                expand_4_bit((value >> 4) as u8 & 0xF),
+                expand_4_bit(value as u8 & 0xF),
+            ];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(2)) {
+            let value = ((quantize_4_bit(pixel[0]) as u16) << 12)
+                | ((quantize_4_bit(pixel[1]) as u16) << 8)
+                | ((quantize_4_bit(pixel[2]) as u16) << 4)
+                | quantize_4_bit(pixel[3]) as u16;
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// 8 bit luminance and 8 bit alpha
+#[derive(Debug)]
+pub struct La8Codec;
+
+impl CteFormatCodec for La8Codec {
+    fn id(&self) -> u32 {
+        5
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        16
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::La8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(2)) {
+            *pixel = [chunk[1], chunk[1], chunk[1], chunk[0]];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(2)) {
+            chunk.copy_from_slice(&[pixel[3], options.luminance(*pixel)]);
+        }
+    }
+}
+
+/// two 8 bit values, decoded as the red and green channels. Used for normal maps.
+#[derive(Debug)]
+pub struct Hilo8Codec;
+
+impl CteFormatCodec for Hilo8Codec {
+    fn id(&self) -> u32 {
+        6
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        16
+    }
+
+    fn has_alpha(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgb8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, chunk) in pixels.iter_mut().zip(data.chunks_exact(2)) {
+            *pixel = [chunk[1], chunk[0], 0, 255];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, chunk) in pixels.iter().zip(out.chunks_exact_mut(2)) {
+            chunk.copy_from_slice(&[pixel[1], pixel[0]]);
+        }
+    }
+}
+
+/// 8 bit luminance
+#[derive(Debug)]
+pub struct L8Codec;
+
+impl CteFormatCodec for L8Codec {
+    fn id(&self) -> u32 {
+        7
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        8
+    }
+
+    fn has_alpha(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::L8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, v) in pixels.iter_mut().zip(data.iter()) {
+            *pixel = [*v, *v, *v, 255];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, v) in pixels.iter().zip(out.iter_mut()) {
+            *v = options.luminance(*pixel);
+        }
+    }
+}
+
+/// 8 bit alpha, decoded as white
+#[derive(Debug)]
+pub struct A8Codec;
+
+impl CteFormatCodec for A8Codec {
+    fn id(&self) -> u32 {
+        8
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        8
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::La8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, v) in pixels.iter_mut().zip(data.iter()) {
+            *pixel = [255, 255, 255, *v];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pixel, v) in pixels.iter().zip(out.iter_mut()) {
+            *v = pixel[3];
+        }
+    }
+}
+
+/// 4 bit luminance (in the high nibble) and 4 bit alpha (in the low nibble)
+#[derive(Debug)]
+pub struct La4Codec;
+
+impl CteFormatCodec for La4Codec {
+    fn id(&self) -> u32 {
+        9
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        8
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::La8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        simd::expand_la4(data, pixels);
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        let mut luma = [0; 64];
+        let mut alpha = [0; 64];
+        for (index, pixel) in pixels.iter().enumerate() {
+            luma[index] = options.luminance(*pixel);
+            alpha[index] = pixel[3];
+        }
+        simd::pack_la4(&luma, &alpha, out);
+    }
+}
+
+/// 4 bit luminance, two pixel per byte
+#[derive(Debug)]
+pub struct L4Codec;
+
+impl CteFormatCodec for L4Codec {
+    fn id(&self) -> u32 {
+        10
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        4
+    }
+
+    fn has_alpha(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::L8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, v) in pixels.iter_mut().zip(unpack_nibbles(data)) {
+            let white = expand_4_bit(v);
+            *pixel = [white, white, white, 255];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        for (pair, v) in pixels.chunks_exact(2).zip(out.iter_mut()) {
+            *v = pack_nibbles(
+                quantize_4_bit(options.luminance(pair[0])),
+                quantize_4_bit(options.luminance(pair[1])),
+            );
+        }
+    }
+}
+
+/// 4 bit alpha, two pixel per byte, decoded as white
+#[derive(Debug)]
+pub struct A4Codec;
+
+impl CteFormatCodec for A4Codec {
+    fn id(&self) -> u32 {
+        11
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        4
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::La8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        for (pixel, v) in pixels.iter_mut().zip(unpack_nibbles(data)) {
+            *pixel = [255, 255, 255, expand_4_bit(v)];
+        }
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], _options: &EncodeOptions, out: &mut [u8]) {
+        for (pair, v) in pixels.chunks_exact(2).zip(out.iter_mut()) {
+            *v = pack_nibbles(quantize_4_bit(pair[0][3]), quantize_4_bit(pair[1][3]));
+        }
+    }
+}
+
+/// ETC1 compressed RGB
+#[derive(Debug)]
+pub struct Etc1Codec;
+
+impl CteFormatCodec for Etc1Codec {
+    fn id(&self) -> u32 {
+        12
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        4
+    }
+
+    fn has_alpha(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgb8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        etc1::decode_tile(data, false, pixels);
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        etc1::encode_tile(pixels, false, options.etc1_quality, out);
+    }
+}
+
+/// ETC1 compressed RGB, with an additional 4 bit alpha
+#[derive(Debug)]
+pub struct Etc1A4Codec;
+
+impl CteFormatCodec for Etc1A4Codec {
+    fn id(&self) -> u32 {
+        13
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        8
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "std")]
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        etc1::decode_tile(data, true, pixels);
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        etc1::encode_tile(pixels, true, options.etc1_quality, out);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{CteFormat, CteHeader, CteImage};
+use image::GenericImageView;
+use serde::Serialize;
+
+/// The structure of a decoded file, as dumped by [`CteImage::to_debug_json`]
+#[derive(Serialize)]
+struct CteDebugInfo<'a> {
+    header: Option<&'a CteHeader>,
+    format: CteFormat,
+    format_name: Option<&'static str>,
+    logical_dimensions: (u32, u32),
+    stored_dimensions: (u32, u32),
+    mipmap_dimensions: Vec<(u32, u32)>,
+    decode_warnings: Vec<String>,
+    /// the tiles, in the order they are stored in
+    tiles: Vec<TileDebugInfo>,
+    pixels: PixelStats,
+}
+
+#[derive(Serialize)]
+struct TileDebugInfo {
+    x: u32,
+    y: u32,
+    /// the offset of the tile in the file, if the image was decoded from one
+    offset: Option<u64>,
+}
+
+#[derive(Serialize, Default)]
+struct PixelStats {
+    /// the minimum, mean and maximum value of the red, green, blue and alpha channels
+    min: [u8; 4],
+    mean: [f64; 4],
+    max: [u8; 4],
+    transparent_pixels: u64,
+    opaque_pixels: u64,
+}
+
+impl PixelStats {
+    fn compute(image: &image::DynamicImage) -> Self {
+        let mut stats = Self {
+            min: [255; 4],
+            ..Self::default()
+        };
+        let mut sum = [0u64; 4];
+        let mut count = 0;
+        for (_, _, pixel) in image.pixels() {
+            for (channel, value) in pixel.0.iter().copied().enumerate() {
+                stats.min[channel] = stats.min[channel].min(value);
+                stats.max[channel] = stats.max[channel].max(value);
+                sum[channel] += value as u64;
+            }
+            match pixel.0[3] {
+                0 => stats.transparent_pixels += 1,
+                255 => stats.opaque_pixels += 1,
+                _ => (),
+            };
+            count += 1;
+        }
+        if count == 0 {
+            stats.min = [0; 4];
+        } else {
+            stats.mean = sum.map(|sum| sum as f64 / count as f64);
+        };
+        stats
+    }
+}
+
+impl CteImage {
+    /// a pretty printed json dump of the header, the offset of each tile and statistics on the
+    /// pixels, to compare the structure of two files or to report a problem with a file
+    pub fn to_debug_json(&self) -> String {
+        let (width, height) = self.stored_dimensions();
+        let width_section = width / 8;
+        let height_section = height / 8;
+        let tile_size = self.original_format.get_tile_size();
+        let tiles = (0..width_section * height_section)
+            .map(|index| {
+                let x = (index % width_section) * 8;
+                let y = (height_section - 1 - index / width_section) * 8;
+                TileDebugInfo {
+                    x,
+                    y,
+                    offset: self
+                        .header
+                        .as_ref()
+                        .and_then(|header| header.tile_offset(tile_size, x, y)),
+                }
+            })
+            .collect();
+        let info = CteDebugInfo {
+            header: self.header.as_ref(),
+            format: self.original_format,
+            format_name: self.original_format.name(),
+            logical_dimensions: self.logical_dimensions(),
+            stored_dimensions: (width, height),
+            mipmap_dimensions: self
+                .mipmaps
+                .iter()
+                .map(|level| level.dimensions())
+                .collect(),
+            decode_warnings: self
+                .decode_warnings
+                .iter()
+                .map(|warning| warning.to_string())
+                .collect(),
+            tiles,
+            pixels: PixelStats::compute(&self.image),
+        };
+        serde_json::to_string_pretty(&info).expect("the debug information should be serializable")
+    }
+}
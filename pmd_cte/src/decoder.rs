@@ -0,0 +1,95 @@
+use crate::reader::PositionTracker;
+use crate::{decoded_color_type, CteDecodeError, CteDecodeWarning, CteFormat, CteHeader, CteImage};
+use crate::{DecodeOptions, CTE_HEADER_SIZE};
+use image::error::{DecodingError, ImageError, ImageFormatHint, ImageResult};
+use image::{ColorType, ImageDecoder};
+use std::io::{Cursor, Read};
+
+/// A decoder for cte file, to be used with the generic function of the `image` crate.
+///
+/// The header is read when creating it, and the pixels when the image is requested. Mipmaps
+/// are ignored.
+pub struct CteDecoder<R> {
+    input: PositionTracker<R>,
+    header: CteHeader,
+    format: CteFormat,
+    warnings: Vec<CteDecodeWarning>,
+    options: DecodeOptions,
+}
+
+impl<R: Read> CteDecoder<R> {
+    pub fn new(input: R) -> Result<Self, CteDecodeError> {
+        Self::with_options(input, DecodeOptions::default())
+    }
+
+    pub fn with_options(input: R, options: DecodeOptions) -> Result<Self, CteDecodeError> {
+        let options = DecodeOptions {
+            mipmaps: false,
+            ..options
+        };
+        let mut input = PositionTracker::new(input);
+        let (header, format, warnings) = CteImage::read_checked_header(&mut input, &options)?;
+        let distance_before_start = header
+            .pixel_start_offset
+            .saturating_sub(CTE_HEADER_SIZE as u32);
+        input.skip(distance_before_start as u64)?;
+        Ok(Self {
+            input,
+            header,
+            format,
+            warnings,
+            options,
+        })
+    }
+
+    pub fn header(&self) -> &CteHeader {
+        &self.header
+    }
+
+    pub fn format(&self) -> CteFormat {
+        self.format
+    }
+
+    /// decode the image, keeping all the cte specific information
+    pub fn decode(mut self) -> Result<CteImage, CteDecodeError> {
+        CteImage::decode_pixels(
+            &mut self.input,
+            self.header,
+            self.format,
+            self.warnings,
+            &self.options,
+        )
+    }
+}
+
+impl<'a, R: Read + 'a> ImageDecoder<'a> for CteDecoder<R> {
+    type Reader = Cursor<Vec<u8>>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        match self.options.logical_size {
+            Some((width, height)) => (width.min(self.header.width), height.min(self.header.height)),
+            None => (self.header.width, self.header.height),
+        }
+    }
+
+    fn color_type(&self) -> ColorType {
+        decoded_color_type(self.format.codec())
+    }
+
+    fn into_reader(self) -> ImageResult<Self::Reader> {
+        let image = self.decode()?;
+        Ok(Cursor::new(image.image.to_bytes()))
+    }
+}
+
+impl From<CteDecodeError> for ImageError {
+    fn from(err: CteDecodeError) -> Self {
+        match err {
+            CteDecodeError::IOError(err, _) => ImageError::IoError(err),
+            err => ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Name("CTE".to_string()),
+                err,
+            )),
+        }
+    }
+}
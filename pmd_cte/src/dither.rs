@@ -0,0 +1,126 @@
+//! Dithering of the channels stored with only 4 bits, applied to the whole image before it is
+//! split in tiles.
+
+use crate::codec::expand_4_bit;
+use crate::{CteFormat, Dithering, EncodeOptions};
+use alloc::vec::Vec;
+
+/// return the dithered pixels of the image, row by row, or None if there is nothing to dither
+pub(crate) fn dither<P>(
+    pixel_at: P,
+    dimensions: (u32, u32),
+    format: CteFormat,
+    options: &EncodeOptions,
+) -> Option<Vec<[u8; 4]>>
+where
+    P: Fn(u32, u32) -> [u8; 4],
+{
+    let (color, alpha) = format.nibble_channels();
+    if options.dithering == Dithering::None || !(color || alpha) {
+        return None;
+    };
+    let luma = color && matches!(format, CteFormat::L4 | CteFormat::La4);
+    let (width, height) = (dimensions.0 as usize, dimensions.1 as usize);
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..dimensions.1 {
+        for x in 0..dimensions.0 {
+            let pixel = pixel_at(x, y);
+            pixels.push(if luma {
+                let white = options.luminance(pixel);
+                [white, white, white, pixel[3]]
+            } else {
+                pixel
+            });
+        }
+    }
+    // for luminance, only the channel 0 is dithered, and copied to the other color channels
+    for channel in 0..4 {
+        let dithered = match channel {
+            0 => color,
+            1 | 2 => color && !luma,
+            _ => alpha,
+        };
+        if !dithered {
+            continue;
+        };
+        match options.dithering {
+            Dithering::None => (),
+            Dithering::FloydSteinberg => floyd_steinberg(&mut pixels, width, height, channel),
+            Dithering::Ordered(size) => ordered(&mut pixels, width, channel, size),
+        }
+    }
+    if luma {
+        for pixel in &mut pixels {
+            pixel[1] = pixel[0];
+            pixel[2] = pixel[0];
+        }
+    };
+    Some(pixels)
+}
+
+/// the 4 bit value whose decoded value is the nearest to `value`, given in 1/16 of the unit
+fn nearest_4_bit(value: i32) -> u8 {
+    (0..16)
+        .min_by_key(|nibble| (expand_4_bit(*nibble) as i32 * 16 - value).abs())
+        .unwrap_or(0)
+}
+
+/// replace a channel by the nearest value that can be stored with 4 bits, spreading the error to
+/// the neighbouring pixels that are yet to be processed
+fn floyd_steinberg(pixels: &mut [[u8; 4]], width: usize, height: usize, channel: usize) {
+    // the error is stored in 1/16 of the unit, to avoid floating point
+    let mut errors = alloc::vec![0i32; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let wanted = pixels[index][channel] as i32 * 16 + errors[index];
+            let nibble = nearest_4_bit(wanted);
+            pixels[index][channel] = expand_4_bit(nibble);
+            let error = wanted - expand_4_bit(nibble) as i32 * 16;
+            let mut spread = |x: usize, y: usize, weight: i32| {
+                if x < width && y < height {
+                    errors[y * width + x] += error * weight / 16;
+                };
+            };
+            spread(x + 1, y, 7);
+            if x > 0 {
+                spread(x - 1, y + 1, 3);
+            };
+            spread(x, y + 1, 5);
+            spread(x + 1, y + 1, 1);
+        }
+    }
+}
+
+/// the value of the Bayer matrix of the given size (a power of two) at the given position
+fn bayer(x: usize, y: usize, size: usize) -> usize {
+    let mut result = 0;
+    let mut bit = 1;
+    while bit < size {
+        let (x_bit, y_bit) = (x & bit != 0, y & bit != 0);
+        let value = match (x_bit, y_bit) {
+            (false, false) => 0,
+            (true, false) => 2,
+            (false, true) => 3,
+            (true, true) => 1,
+        };
+        result = result * 4 + value;
+        bit <<= 1;
+    }
+    result
+}
+
+/// replace a channel by a value that can be stored with 4 bits, offsetting it by a threshold
+/// depending on its position before rounding
+fn ordered(pixels: &mut [[u8; 4]], width: usize, channel: usize, size: u32) {
+    let size = size.clamp(2, 16).next_power_of_two() as usize;
+    let step = expand_4_bit(1) as i32;
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let (x, y) = (index % width, index / width);
+        // the threshold, in 1/16 of the unit, is between -step/2 and step/2
+        let threshold = ((2 * bayer(x % size, y % size, size) + 1) as i32 * step * 16)
+            / (2 * size * size) as i32
+            - step * 8;
+        pixel[channel] = expand_4_bit(nearest_4_bit(pixel[channel] as i32 * 16 + threshold));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{CteEncodeError, CteFormat, CteImage, EncodeOptions};
+use image::error::{
+    EncodingError, ImageError, ImageFormatHint, ImageResult, ParameterError, ParameterErrorKind,
+    UnsupportedError, UnsupportedErrorKind,
+};
+use image::{ColorType, DynamicImage, ImageBuffer, ImageEncoder};
+use std::io::Write;
+
+/// An encoder for cte file, to be used with the generic function of the `image` crate
+pub struct CteEncoder<W> {
+    out: W,
+    format: CteFormat,
+    options: EncodeOptions,
+}
+
+impl<W: Write> CteEncoder<W> {
+    pub fn new(out: W, format: CteFormat) -> Self {
+        Self::with_options(out, format, EncodeOptions::default())
+    }
+
+    pub fn with_options(out: W, format: CteFormat, options: EncodeOptions) -> Self {
+        Self {
+            out,
+            format,
+            options,
+        }
+    }
+}
+
+fn format_hint() -> ImageFormatHint {
+    ImageFormatHint::Name("CTE".to_string())
+}
+
+impl<W: Write> ImageEncoder for CteEncoder<W> {
+    fn write_image(
+        mut self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ColorType,
+    ) -> ImageResult<()> {
+        let buf = buf.to_vec();
+        let image = match color_type {
+            ColorType::L8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+            }
+            ColorType::La8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8)
+            }
+            ColorType::Rgb8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+            }
+            ColorType::Rgba8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+            }
+            _ => {
+                return Err(ImageError::Unsupported(
+                    UnsupportedError::from_format_and_kind(
+                        format_hint(),
+                        UnsupportedErrorKind::Color(color_type.into()),
+                    ),
+                ))
+            }
+        }
+        .ok_or_else(|| {
+            ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            ))
+        })?;
+        Ok(CteImage::new(self.format, image).encode_cte_with(&mut self.out, &self.options)?)
+    }
+}
+
+impl From<CteEncodeError> for ImageError {
+    fn from(err: CteEncodeError) -> Self {
+        match err {
+            CteEncodeError::IOError(err) => ImageError::IoError(err),
+            err => ImageError::Encoding(EncodingError::new(format_hint(), err)),
+        }
+    }
+}
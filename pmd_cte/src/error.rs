@@ -0,0 +1,180 @@
+use crate::CteFormat;
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+use thiserror::Error;
+
+/// An error that occured while decoding a file. The last field of each variant is the offset
+/// (relative to the start of the cte file) the problem was detected at.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CteDecodeError {
+    #[cfg(feature = "std")]
+    #[error("An issue occured when reading the file at offset {1:#x}")]
+    IOError(#[source] io::Error, u64),
+    #[error("the file end before the end of the header (at offset {0:#x})")]
+    UnexpectedEnd(u64),
+    #[error("the header of the cte file doesn't correspond to the expected one (\\x0cte): {0:?} at offset {1:#x}")]
+    InvalideHeader([u8; 4], u64),
+    #[error("the cte image format with the id {0} isn't supported (at offset {1:#x})")]
+    UnsuportedFormat(u32, u64),
+    #[error("the cte image content is mixed with the header. That shouldn't happen. (the content start at {0}, read at offset {1:#x})")]
+    ImageStartTooSoon(u32, u64),
+    #[error(
+        "the number of byte by pixel in the file is invalid (the size is {0}, for the format {1:?}) at offset {2:#x}"
+    )]
+    PixelLenghtInvalid(u32, CteFormat, u64),
+    #[error("the width {0} of the image isn't a multiple of 8 (at offset {1:#x})")]
+    WidthNotMultiple8(u32, u64),
+    #[error("the height {0} of the image isn't a multiple of 8 (at offset {1:#x})")]
+    HeightNotMultiple8(u32, u64),
+    #[error("the buffer to decode the image into is too small (it is {0} bytes long, but {1} are needed) (at offset {2:#x})")]
+    BufferTooSmall(usize, usize, u64),
+    #[error("the pixel data is {0} bytes long, but {1} are needed (at offset {2:#x})")]
+    PixelDataTooShort(u64, u64, u64),
+    #[error("the image of {0}×{1} pixels is too big to be stored in memory (at offset {2:#x})")]
+    ImageTooBig(u32, u32, u64),
+    #[error("internal error : {0} (at offset {1:#x})")]
+    InternalError(&'static str, u64),
+    #[cfg(feature = "std")]
+    #[error("the texture is rotated (with the swizzle mode {0}), which isn't supported (at offset {1:#x})")]
+    ClimRotated(u8, u64),
+    #[cfg(feature = "sir0")]
+    #[error("the sir0 container point to its content at {0:#x}, which is outside of it (at offset {1:#x})")]
+    Sir0PointerInvalid(u32, u64),
+}
+
+impl CteDecodeError {
+    /// the offset, relative to the start of the cte file, the error occured at
+    pub fn offset(&self) -> u64 {
+        match self {
+            #[cfg(feature = "std")]
+            Self::IOError(_, offset) => *offset,
+            Self::UnexpectedEnd(offset)
+            | Self::InvalideHeader(_, offset)
+            | Self::UnsuportedFormat(_, offset)
+            | Self::ImageStartTooSoon(_, offset)
+            | Self::PixelLenghtInvalid(_, _, offset)
+            | Self::WidthNotMultiple8(_, offset)
+            | Self::HeightNotMultiple8(_, offset)
+            | Self::InternalError(_, offset) => *offset,
+            #[cfg(feature = "std")]
+            Self::ClimRotated(_, offset) => *offset,
+            #[cfg(feature = "sir0")]
+            Self::Sir0PointerInvalid(_, offset) => *offset,
+            Self::BufferTooSmall(_, _, offset) | Self::PixelDataTooShort(_, _, offset) => *offset,
+            Self::ImageTooBig(_, _, offset) => *offset,
+        }
+    }
+}
+
+/// A problem with a file that doesn't prevent it from being decoded in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CteDecodeWarning {
+    /// the number of bit per pixel in the header doesn't match the format
+    PixelLengthMismatch { found: u32, expected: u32 },
+    /// the pixel data start inside the header
+    ImageStartTooSoon(u32),
+    /// there is this number of byte after the image (and its mipmaps) that were ignored
+    TrailingData(u64),
+    /// a dimension of the image is either 0 or bigger than what the 3DS can display
+    DimensionOutOfRange(u32),
+}
+
+impl fmt::Display for CteDecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PixelLengthMismatch { found, expected } => write!(
+                f,
+                "pixel_length mismatch (found {}, expected {}), continuing",
+                found, expected
+            ),
+            Self::ImageStartTooSoon(offset) => write!(
+                f,
+                "the pixel data start at {}, inside the header, continuing",
+                offset
+            ),
+            Self::TrailingData(length) => {
+                write!(f, "{} bytes of unknown data at the end of the file", length)
+            }
+            Self::DimensionOutOfRange(dimension) => write!(
+                f,
+                "the dimension {} is outside of the range supported by the 3DS",
+                dimension
+            ),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CteEncodeError {
+    #[cfg(feature = "std")]
+    #[error("An issue occured while writing the file")]
+    IOError(#[from] io::Error),
+    #[error("the width {0} of the image isn't a multiple of 8")]
+    WidthNotMultiple8(u32),
+    #[error("the height {0} of the image isn't a multiple of 8")]
+    HeightNotMultiple8(u32),
+    #[error("the pixel data can't start at {0}, as it would overlap the header")]
+    ImageStartTooSoon(u32),
+    #[error("the image contain transparent pixel, but the {0:?} format doesn't store alpha")]
+    AlphaDiscarded(CteFormat),
+    #[error("the tile is {0} bytes long, but the format store tiles of {1} bytes")]
+    TileSizeInvalid(usize, usize),
+    #[error("the pixel buffer is {0} bytes long, but it should be {1} bytes long")]
+    PixelBufferSizeInvalid(usize, usize),
+    #[error("the image of {0}×{1} pixels is too big to be stored in memory")]
+    ImageTooBig(u32, u32),
+    #[error("the {0:?} format can't be stored in a {1} file")]
+    FormatNotSupportedByContainer(CteFormat, &'static str),
+    #[error(
+        "the dimension {0} of the image is bigger than the 1024 pixels of the biggest texture"
+    )]
+    DimensionTooBig(u32),
+    #[error("the encoded image couldn't be decoded back")]
+    EncodedImageInvalid(#[source] CteDecodeError),
+}
+
+/// The error returned when parsing an unknown format name
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("the cte format {0:?} doesn't exist")]
+pub struct ParseCteFormatError(pub String);
+
+/// An error that occured while decoding a cte file stored in a FARC archive
+#[cfg(feature = "farc")]
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CteFarcError {
+    #[error("failed to read the file from the FARC archive")]
+    Farc(#[from] pmd_farc::FarcError),
+    #[error("failed to read the file from the FARC archive")]
+    IOError(#[from] io::Error),
+    #[error("failed to decode the cte image")]
+    Decode(#[from] CteDecodeError),
+}
+
+/// An error that occured while accessing a glyph of an image
+#[cfg(feature = "std")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CteGlyphError {
+    #[error("the image doesn't have a glyph grid")]
+    NoGlyphGrid,
+    #[error("there isn't any glyph {0}, as the grid has {1} glyphs")]
+    IndexOutOfRange(u32, u32),
+    #[error("the cell of the glyph {0} is outside of the image")]
+    OutsideImage(u32),
+    #[error("the glyph is {0:?} pixels, bigger than the {1:?} cells of the grid")]
+    GlyphTooBig((u32, u32), (u32, u32)),
+}
+
+/// Either a decoding or an encoding error, for applications doing both
+#[derive(Error, Debug)]
+pub enum CteError {
+    #[error("failed to decode the cte image")]
+    Decode(#[from] CteDecodeError),
+    #[error("failed to encode the cte image")]
+    Encode(#[from] CteEncodeError),
+}
@@ -0,0 +1,363 @@
+//! ETC1 block compression, as used by the 3DS.
+//!
+//! An 8×8 tile contain four 4×4 ETC1 block, in the order top-left, top-right, bottom-left,
+//! bottom-right (in the GPU coordinate, where the first row is the bottom of the displayed image).
+//! Each block is a 64 bit little endian integer, which follow the standard ETC1 bit layout.
+//!
+//! With ETC1A4, each block is preceded by a 64 bit little endian integer containing the 4 bit
+//! alpha of its 16 pixels, indexed the same way as the ETC1 pixels.
+
+use crate::codec::{self, quantize_4_bit};
+use crate::swizzle::morton_index;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// the size of a single compressed 4×4 block
+pub const ETC1_BLOCK_SIZE: usize = 8;
+
+const MODIFIER_TABLE: [[i16; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+fn expand_4_bit(value: u64) -> u8 {
+    let value = (value & 0xF) as u8;
+    (value << 4) | value
+}
+
+fn expand_5_bit(value: u64) -> u8 {
+    let value = (value & 0x1F) as u8;
+    (value << 3) | (value >> 2)
+}
+
+/// Decode a single ETC1 block. The result is indexed by `x * 4 + y`, like the pixel index of the
+/// ETC1 format itself.
+pub fn decode_block(block: u64) -> [[u8; 3]; 16] {
+    let diff = (block >> 33) & 1 == 1;
+    let flip = (block >> 32) & 1 == 1;
+    let tables = [
+        MODIFIER_TABLE[((block >> 37) & 0x7) as usize],
+        MODIFIER_TABLE[((block >> 34) & 0x7) as usize],
+    ];
+
+    let base_colors = if diff {
+        let mut first = [0; 3];
+        let mut second = [0; 3];
+        for channel in 0..3 {
+            let shift = 59 - channel * 8;
+            let base = (block >> shift) & 0x1F;
+            // the difference is a signed 3 bit integer
+            let delta = (((block >> (shift - 3)) & 0x7) as i64) << 61 >> 61;
+            first[channel as usize] = expand_5_bit(base);
+            second[channel as usize] = expand_5_bit((base as i64 + delta) as u64);
+        }
+        [first, second]
+    } else {
+        let mut first = [0; 3];
+        let mut second = [0; 3];
+        for channel in 0..3 {
+            let shift = 60 - channel * 8;
+            first[channel as usize] = expand_4_bit(block >> shift);
+            second[channel as usize] = expand_4_bit(block >> (shift - 4));
+        }
+        [first, second]
+    };
+
+    let mut result = [[0; 3]; 16];
+    for x in 0..4 {
+        for y in 0..4 {
+            let index = x * 4 + y;
+            let subblock = if flip { y >= 2 } else { x >= 2 } as usize;
+            let msb = (block >> (16 + index)) & 1;
+            let lsb = (block >> index) & 1;
+            let table = tables[subblock];
+            let modifier = match (msb, lsb) {
+                (0, 0) => table[0],
+                (0, _) => table[1],
+                (_, 0) => -table[0],
+                (_, _) => -table[1],
+            };
+            let base = base_colors[subblock];
+            result[index] = [
+                (base[0] as i16 + modifier).clamp(0, 255) as u8,
+                (base[1] as i16 + modifier).clamp(0, 255) as u8,
+                (base[2] as i16 + modifier).clamp(0, 255) as u8,
+            ];
+        }
+    }
+    result
+}
+
+/// Decode an 8×8 ETC1 tile (32 bytes, or 64 bytes for ETC1A4) into its pixels, in the same order
+/// as the non-compressed formats.
+pub fn decode_tile(data: &[u8], has_alpha: bool, pixels: &mut [[u8; 4]; 64]) {
+    let block_size = if has_alpha {
+        ETC1_BLOCK_SIZE * 2
+    } else {
+        ETC1_BLOCK_SIZE
+    };
+    for (block_nb, block) in data.chunks_exact(block_size).take(4).enumerate() {
+        let (alpha, block) = if has_alpha {
+            (
+                read_u64(&block[..ETC1_BLOCK_SIZE]),
+                &block[ETC1_BLOCK_SIZE..],
+            )
+        } else {
+            (u64::MAX, block)
+        };
+        let decoded = decode_block(read_u64(block));
+        let base_x = (block_nb as u32 % 2) * 4;
+        let base_y = (block_nb as u32 / 2) * 4;
+        for x in 0..4 {
+            for y in 0..4 {
+                let index = x * 4 + y;
+                let color = decoded[index as usize];
+                let alpha = codec::expand_4_bit(((alpha >> (4 * index)) & 0xF) as u8);
+                pixels[morton_index(base_x + x, base_y + y)] =
+                    [color[0], color[1], color[2], alpha];
+            }
+        }
+    }
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(data);
+    u64::from_le_bytes(bytes)
+}
+
+/// The trade-off between speed and quality when compressing to ETC1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Etc1Quality {
+    /// use the average color of each half of a block as its base color
+    #[default]
+    Fast,
+    /// also try every base color close to the average one. Significantly slower.
+    High,
+}
+
+fn quantize(value: u32, bits: u32) -> i32 {
+    let max = (1 << bits) - 1;
+    ((value * max + 127) / 255) as i32
+}
+
+fn expand(value: i32, bits: u32) -> u8 {
+    if bits == 4 {
+        expand_4_bit(value as u64)
+    } else {
+        expand_5_bit(value as u64)
+    }
+}
+
+/// the result of the compression of an half of a block
+#[derive(Clone, Copy)]
+struct SubblockEncoding {
+    error: u32,
+    table: u8,
+    modifiers: [u8; 8],
+}
+
+/// find the best modifier table for the given pixel and (already quantized) base color
+fn encode_subblock(pixels: &[[u8; 3]; 8], base: [i32; 3], bits: u32) -> SubblockEncoding {
+    let base = [
+        expand(base[0], bits) as i16,
+        expand(base[1], bits) as i16,
+        expand(base[2], bits) as i16,
+    ];
+    let mut best = SubblockEncoding {
+        error: u32::MAX,
+        table: 0,
+        modifiers: [0; 8],
+    };
+    for (table_nb, table) in MODIFIER_TABLE.iter().enumerate() {
+        let modifiers = [table[0], table[1], -table[0], -table[1]];
+        let mut result = SubblockEncoding {
+            error: 0,
+            table: table_nb as u8,
+            modifiers: [0; 8],
+        };
+        for (pixel, chosen) in pixels.iter().zip(result.modifiers.iter_mut()) {
+            let mut best_pixel_error = u32::MAX;
+            for (modifier_nb, modifier) in modifiers.iter().enumerate() {
+                let mut pixel_error = 0;
+                for channel in 0..3 {
+                    let value = (base[channel] + modifier).clamp(0, 255);
+                    let diff = (value - pixel[channel] as i16) as i32;
+                    pixel_error += (diff * diff) as u32;
+                }
+                if pixel_error < best_pixel_error {
+                    best_pixel_error = pixel_error;
+                    *chosen = modifier_nb as u8;
+                }
+            }
+            result.error += best_pixel_error;
+        }
+        if result.error < best.error {
+            best = result;
+        }
+    }
+    best
+}
+
+/// return the base colors worth trying for the given pixels, quantized to `bits` bits
+fn candidate_bases(pixels: &[[u8; 3]; 8], bits: u32, quality: Etc1Quality) -> Vec<[i32; 3]> {
+    let mut sum = [0; 3];
+    for pixel in pixels {
+        for channel in 0..3 {
+            sum[channel] += pixel[channel] as u32;
+        }
+    }
+    let average = [
+        quantize(sum[0] / 8, bits),
+        quantize(sum[1] / 8, bits),
+        quantize(sum[2] / 8, bits),
+    ];
+    match quality {
+        Etc1Quality::Fast => vec![average],
+        Etc1Quality::High => {
+            let max = (1 << bits) - 1;
+            let mut result = Vec::with_capacity(27);
+            for r in -1..=1 {
+                for g in -1..=1 {
+                    for b in -1..=1 {
+                        let candidate = [average[0] + r, average[1] + g, average[2] + b];
+                        if candidate.iter().all(|c| (0..=max).contains(c)) {
+                            result.push(candidate);
+                        }
+                    }
+                }
+            }
+            result
+        }
+    }
+}
+
+fn best_subblock(
+    pixels: &[[u8; 3]; 8],
+    candidates: &[[i32; 3]],
+    bits: u32,
+) -> Option<([i32; 3], SubblockEncoding)> {
+    candidates
+        .iter()
+        .map(|base| (*base, encode_subblock(pixels, *base, bits)))
+        .min_by_key(|(_, encoding)| encoding.error)
+}
+
+/// Encode a 4×4 block. The pixels are indexed by `x * 4 + y`.
+pub fn encode_block(pixels: &[[u8; 3]; 16], quality: Etc1Quality) -> u64 {
+    let mut best: Option<(u32, u64)> = None;
+    for flip in [false, true] {
+        let mut halves = [[[0; 3]; 8]; 2];
+        let mut counts = [0; 2];
+        for x in 0..4 {
+            for y in 0..4 {
+                let half = if flip { y >= 2 } else { x >= 2 } as usize;
+                halves[half][counts[half]] = pixels[x * 4 + y];
+                counts[half] += 1;
+            }
+        }
+
+        // individual mode
+        let first = best_subblock(&halves[0], &candidate_bases(&halves[0], 4, quality), 4);
+        let second = best_subblock(&halves[1], &candidate_bases(&halves[1], 4, quality), 4);
+        if let (Some(first), Some(second)) = (first, second) {
+            let error = first.1.error + second.1.error;
+            if best.is_none_or(|(best_error, _)| error < best_error) {
+                let mut block = 0;
+                for channel in 0..3 {
+                    let shift = 60 - channel * 8;
+                    block |= (first.0[channel] as u64) << shift;
+                    block |= (second.0[channel] as u64) << (shift - 4);
+                }
+                best = Some((
+                    error,
+                    finish_block(block, false, flip, &[first.1, second.1]),
+                ));
+            }
+        }
+
+        // differential mode
+        let first = best_subblock(&halves[0], &candidate_bases(&halves[0], 5, quality), 5);
+        if let Some(first) = first {
+            let second_candidates: Vec<[i32; 3]> = candidate_bases(&halves[1], 5, quality)
+                .into_iter()
+                .filter(|candidate| {
+                    (0..3)
+                        .all(|channel| (-4..=3).contains(&(candidate[channel] - first.0[channel])))
+                })
+                .collect();
+            if let Some(second) = best_subblock(&halves[1], &second_candidates, 5) {
+                let error = first.1.error + second.1.error;
+                if best.is_none_or(|(best_error, _)| error < best_error) {
+                    let mut block = 0;
+                    for channel in 0..3 {
+                        let shift = 59 - channel * 8;
+                        let delta = second.0[channel] - first.0[channel];
+                        block |= (first.0[channel] as u64) << shift;
+                        block |= ((delta & 0x7) as u64) << (shift - 3);
+                    }
+                    best = Some((error, finish_block(block, true, flip, &[first.1, second.1])));
+                }
+            }
+        }
+    }
+    // the individual mode can always encode a block
+    best.unwrap().1
+}
+
+/// add the table, the flags and the pixels modifier to a block which contain only the base colors
+fn finish_block(mut block: u64, diff: bool, flip: bool, halves: &[SubblockEncoding; 2]) -> u64 {
+    block |= (halves[0].table as u64) << 37;
+    block |= (halves[1].table as u64) << 34;
+    block |= (diff as u64) << 33;
+    block |= (flip as u64) << 32;
+    let mut counts = [0; 2];
+    for x in 0..4 {
+        for y in 0..4 {
+            let half = if flip { y >= 2 } else { x >= 2 } as usize;
+            let modifier = halves[half].modifiers[counts[half]] as u64;
+            counts[half] += 1;
+            let index = x * 4 + y;
+            block |= (modifier >> 1) << (16 + index);
+            block |= (modifier & 1) << index;
+        }
+    }
+    block
+}
+
+/// Encode an 8×8 tile, with the pixels in the same order as the one returned by [`decode_tile`].
+/// `out` should be 32 bytes long, or 64 bytes for ETC1A4.
+pub fn encode_tile(pixels: &[[u8; 4]; 64], has_alpha: bool, quality: Etc1Quality, out: &mut [u8]) {
+    let block_size = if has_alpha {
+        ETC1_BLOCK_SIZE * 2
+    } else {
+        ETC1_BLOCK_SIZE
+    };
+    for (block_nb, chunk) in out.chunks_exact_mut(block_size).take(4).enumerate() {
+        let base_x = (block_nb as u32 % 2) * 4;
+        let base_y = (block_nb as u32 / 2) * 4;
+        let mut block_pixels = [[0; 3]; 16];
+        let mut alpha = 0;
+        for x in 0..4 {
+            for y in 0..4 {
+                let index = x * 4 + y;
+                let pixel = pixels[morton_index(base_x + x, base_y + y)];
+                block_pixels[index as usize] = [pixel[0], pixel[1], pixel[2]];
+                alpha |= (quantize_4_bit(pixel[3]) as u64) << (4 * index);
+            }
+        }
+        let chunk = if has_alpha {
+            chunk[..ETC1_BLOCK_SIZE].copy_from_slice(&alpha.to_le_bytes());
+            &mut chunk[ETC1_BLOCK_SIZE..]
+        } else {
+            chunk
+        };
+        chunk.copy_from_slice(&encode_block(&block_pixels, quality).to_le_bytes());
+    }
+}
@@ -0,0 +1,89 @@
+//! Decode the cte files stored in FARC archives, without extracting them first. With the `sir0`
+//! feature, the files wrapped in a SIR0 container are also decoded.
+
+use crate::header::CTE_HEADER;
+use crate::{CteFarcError, CteImage, DecodeOptions};
+pub use pmd_farc::Farc;
+use std::io::{Read, Seek};
+
+/// A cte file of a FARC archive
+pub struct CteFarcEntry {
+    /// the hash of the name of the file, which identify it in the archive
+    pub hash: u32,
+    /// the name of the file, if it is known
+    pub name: Option<String>,
+    pub image: CteImage,
+}
+
+/// return true if the data is a cte file (possibly wrapped in a SIR0 container)
+fn is_cte(data: &[u8]) -> bool {
+    #[cfg(feature = "sir0")]
+    if crate::sir0::is_sir0(data) {
+        return crate::sir0::unwrap_sir0(data)
+            .map(|content| content.starts_with(&CTE_HEADER))
+            .unwrap_or(false);
+    };
+    data.starts_with(&CTE_HEADER)
+}
+
+fn decode_entry(data: &[u8], options: &DecodeOptions) -> Result<CteImage, CteFarcError> {
+    #[cfg(feature = "sir0")]
+    let image = CteImage::decode_cte_sir0_with(data, options)?;
+    #[cfg(not(feature = "sir0"))]
+    let image = CteImage::decode_cte_with(&mut std::io::Cursor::new(data), options)?;
+    Ok(image)
+}
+
+fn read_entry<F: Read + Seek>(archive: &Farc<F>, hash: u32) -> Result<Vec<u8>, CteFarcError> {
+    let mut data = Vec::new();
+    archive.get_hashed_file(hash)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// iterate over the cte files of the archive, decoding each one when it is reached. The other
+/// files are skipped.
+pub fn cte_entries<F: Read + Seek>(
+    archive: &Farc<F>,
+) -> impl Iterator<Item = Result<CteFarcEntry, CteFarcError>> + '_ {
+    cte_entries_with(archive, DecodeOptions::default())
+}
+
+pub fn cte_entries_with<F: Read + Seek>(
+    archive: &Farc<F>,
+    options: DecodeOptions,
+) -> impl Iterator<Item = Result<CteFarcEntry, CteFarcError>> + '_ {
+    archive.iter().filter_map(move |(hash, name)| {
+        let data = match read_entry(archive, hash) {
+            Ok(data) => data,
+            Err(err) => return Some(Err(err)),
+        };
+        if !is_cte(&data) {
+            return None;
+        };
+        Some(decode_entry(&data, &options).map(|image| CteFarcEntry {
+            hash,
+            name: name.cloned(),
+            image,
+        }))
+    })
+}
+
+impl CteImage {
+    /// decode the file with the given name of a FARC archive
+    pub fn decode_from_farc<F: Read + Seek>(
+        archive: &Farc<F>,
+        name: &str,
+    ) -> Result<CteImage, CteFarcError> {
+        Self::decode_from_farc_with(archive, name, &DecodeOptions::default())
+    }
+
+    pub fn decode_from_farc_with<F: Read + Seek>(
+        archive: &Farc<F>,
+        name: &str,
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteFarcError> {
+        let mut data = Vec::new();
+        archive.get_named_file(name)?.read_to_end(&mut data)?;
+        decode_entry(&data, options)
+    }
+}
@@ -0,0 +1,43 @@
+//! Replacements for `image::open` and `DynamicImage::save` that also handle cte files, as the
+//! `image` crate doesn't have a way to add formats to them
+
+use crate::header::CTE_HEADER;
+use crate::{CteDecoder, CteFormat, CteImage, EncodeOptions};
+use image::{DynamicImage, ImageResult};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// open the image at `path`, decoding it as a cte file if it start with the cte magic, or with
+/// `image::open` otherwise. Mipmaps are ignored.
+pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<DynamicImage> {
+    let path = path.as_ref();
+    let mut input = BufReader::new(File::open(path)?);
+    if !input.fill_buf()?.starts_with(&CTE_HEADER) {
+        return image::open(path);
+    };
+    DynamicImage::from_decoder(CteDecoder::new(input)?)
+}
+
+/// save the image at `path`. If its extension is `img`, it is encoded as a cte file, in the
+/// smallest format that store it without loss (like A8 for a decoded font), and padded with
+/// transparent pixels if its dimensions aren't multiples of 8. Otherwise, it is saved with
+/// `DynamicImage::save`.
+pub fn save<P: AsRef<Path>>(image: &DynamicImage, path: P) -> ImageResult<()> {
+    let path = path.as_ref();
+    let is_cte = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("img"));
+    if !is_cte {
+        return image.save(path);
+    };
+    let mut data = Vec::new();
+    let format = CteFormat::choose_for_with(image, f64::INFINITY);
+    let options = EncodeOptions {
+        pad: true,
+        ..EncodeOptions::default()
+    };
+    CteImage::new(format, image.clone()).encode_cte_with(&mut data, &options)?;
+    std::fs::write(path, data)?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use crate::codec::*;
+use crate::ParseCteFormatError;
+use alloc::string::ToString;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+
+/// A format a cte image can be stored in
+#[derive(Debug, Clone, Copy)]
+pub enum CteFormat {
+    Rgba8888,
+    Rgb888,
+    Rgba5551,
+    Rgb565,
+    Rgba4444,
+    La8,
+    Hilo8,
+    L8,
+    A8,
+    La4,
+    L4,
+    A4,
+    Etc1,
+    Etc1A4,
+    /// a format handled by a codec that isn't part of this crate. Two formats are considered
+    /// equal if they have the same id.
+    Custom(&'static dyn CteFormatCodec),
+}
+
+impl CteFormat {
+    /// all the formats supported by this crate
+    pub const BUILTIN: [CteFormat; 14] = [
+        Self::Rgba8888,
+        Self::Rgb888,
+        Self::Rgba5551,
+        Self::Rgb565,
+        Self::Rgba4444,
+        Self::La8,
+        Self::Hilo8,
+        Self::L8,
+        Self::A8,
+        Self::La4,
+        Self::L4,
+        Self::A4,
+        Self::Etc1,
+        Self::Etc1A4,
+    ];
+
+    /// return the built-in format with the given id, if it exist
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::BUILTIN.iter().find(|f| f.get_id() == id).copied()
+    }
+
+    /// the codec used to encode and decode tiles of this format
+    pub fn codec(&self) -> &'static dyn CteFormatCodec {
+        match self {
+            Self::Rgba8888 => &Rgba8888Codec,
+            Self::Rgb888 => &Rgb888Codec,
+            Self::Rgba5551 => &Rgba5551Codec,
+            Self::Rgb565 => &Rgb565Codec,
+            Self::Rgba4444 => &Rgba4444Codec,
+            Self::La8 => &La8Codec,
+            Self::Hilo8 => &Hilo8Codec,
+            Self::L8 => &L8Codec,
+            Self::A8 => &A8Codec,
+            Self::La4 => &La4Codec,
+            Self::L4 => &L4Codec,
+            Self::A4 => &A4Codec,
+            Self::Etc1 => &Etc1Codec,
+            Self::Etc1A4 => &Etc1A4Codec,
+            Self::Custom(codec) => *codec,
+        }
+    }
+
+    /// the lowercase name of a built-in format, as accepted by [`CteFormat::from_str`]
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Rgba8888 => "rgba8888",
+            Self::Rgb888 => "rgb888",
+            Self::Rgba5551 => "rgba5551",
+            Self::Rgb565 => "rgb565",
+            Self::Rgba4444 => "rgba4444",
+            Self::La8 => "la8",
+            Self::Hilo8 => "hilo8",
+            Self::L8 => "l8",
+            Self::A8 => "a8",
+            Self::La4 => "la4",
+            Self::L4 => "l4",
+            Self::A4 => "a4",
+            Self::Etc1 => "etc1",
+            Self::Etc1A4 => "etc1a4",
+            Self::Custom(_) => return None,
+        })
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.codec().id()
+    }
+
+    pub fn check_pixel_lenght_bit(&self, lenght: u32) -> bool {
+        lenght == self.get_pixel_length_bit()
+    }
+
+    pub fn get_pixel_length_bit(&self) -> u32 {
+        self.codec().bits_per_pixel()
+    }
+
+    /// the channels of the format that are stored with only 4 bits, as (color, alpha)
+    pub(crate) fn nibble_channels(&self) -> (bool, bool) {
+        match self {
+            Self::L4 => (true, false),
+            Self::La4 | Self::Rgba4444 => (true, true),
+            Self::A4 | Self::Etc1A4 => (false, true),
+            _ => (false, false),
+        }
+    }
+
+    /// true if the format store an alpha channel
+    pub fn has_alpha(&self) -> bool {
+        self.codec().has_alpha()
+    }
+
+    /// the number of byte used to store a 8×8 tile
+    pub fn get_tile_size(&self) -> usize {
+        self.codec().tile_size()
+    }
+}
+
+impl PartialEq for CteFormat {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_id() == other.get_id()
+    }
+}
+
+impl Eq for CteFormat {}
+
+impl Hash for CteFormat {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_id().hash(state)
+    }
+}
+
+/// parse the name of a built-in format, ignoring case
+impl FromStr for CteFormat {
+    type Err = ParseCteFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::BUILTIN
+            .iter()
+            .find(|f| f.name().is_some_and(|name| name.eq_ignore_ascii_case(s)))
+            .copied()
+            .ok_or_else(|| ParseCteFormatError(s.to_string()))
+    }
+}
+
+/// formats are serialized as their id. Only built-in formats can be deserialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CteFormat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.get_id())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CteFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = u32::deserialize(deserializer)?;
+        Self::from_id(id).ok_or_else(|| {
+            serde::de::Error::custom(alloc::format!(
+                "the cte image format with the id {} isn't supported",
+                id
+            ))
+        })
+    }
+}
@@ -0,0 +1,94 @@
+//! Access to the glyphs of a font file, laid out on a grid
+
+use crate::{CteGlyphError, CteImage};
+use image::{DynamicImage, GenericImage, GenericImageView, Rgba};
+
+/// The layout of the glyphs of a font file: `count` cells of the same size, starting at the top
+/// left of the image, from left to right then from top to bottom. The number of cells of each
+/// row is the number that fit in the width of the image.
+///
+/// The files don't store it, so it should be given by the user, or read from a sidecar file
+/// (it can be serialized with the `serde` feature).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlyphGrid {
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub count: u32,
+}
+
+impl GlyphGrid {
+    pub fn new(cell_width: u32, cell_height: u32, count: u32) -> Self {
+        Self {
+            cell_width,
+            cell_height,
+            count,
+        }
+    }
+
+    /// the grid with as many cells as fit in an image of these dimensions
+    pub fn fill(cell_width: u32, cell_height: u32, dimensions: (u32, u32)) -> Self {
+        let columns = dimensions.0.checked_div(cell_width).unwrap_or(0);
+        let rows = dimensions.1.checked_div(cell_height).unwrap_or(0);
+        Self::new(cell_width, cell_height, columns * rows)
+    }
+
+    /// the position of the top left pixel of the cell of the glyph `index`, in an image
+    /// `image_width` pixels wide
+    pub fn cell_position(&self, index: u32, image_width: u32) -> Option<(u32, u32)> {
+        let columns = image_width.checked_div(self.cell_width)?;
+        if index >= self.count || columns == 0 {
+            return None;
+        };
+        Some((
+            index % columns * self.cell_width,
+            index / columns * self.cell_height,
+        ))
+    }
+}
+
+impl CteImage {
+    /// the position of the cell of the glyph `index`, checking it is inside the image
+    fn glyph_cell(&self, index: u32) -> Result<(u32, u32, GlyphGrid), CteGlyphError> {
+        let grid = self.glyph_grid.ok_or(CteGlyphError::NoGlyphGrid)?;
+        if index >= grid.count {
+            return Err(CteGlyphError::IndexOutOfRange(index, grid.count));
+        };
+        let (width, height) = self.image.dimensions();
+        match grid.cell_position(index, width) {
+            Some((x, y)) if y + grid.cell_height <= height => Ok((x, y, grid)),
+            _ => Err(CteGlyphError::OutsideImage(index)),
+        }
+    }
+
+    /// the content of the cell of the glyph `index` of [`CteImage::glyph_grid`]
+    pub fn glyph(&self, index: u32) -> Result<DynamicImage, CteGlyphError> {
+        let (x, y, grid) = self.glyph_cell(index)?;
+        Ok(self.image.crop_imm(x, y, grid.cell_width, grid.cell_height))
+    }
+
+    /// replace the content of the cell of the glyph `index` of [`CteImage::glyph_grid`]. A glyph
+    /// smaller than the cell is put at its top left, the rest of the cell being transparent. The
+    /// mipmaps aren't updated.
+    pub fn set_glyph(&mut self, index: u32, glyph: &DynamicImage) -> Result<(), CteGlyphError> {
+        let (x, y, grid) = self.glyph_cell(index)?;
+        let (glyph_width, glyph_height) = glyph.dimensions();
+        if glyph_width > grid.cell_width || glyph_height > grid.cell_height {
+            return Err(CteGlyphError::GlyphTooBig(
+                (glyph_width, glyph_height),
+                (grid.cell_width, grid.cell_height),
+            ));
+        };
+        for cell_y in 0..grid.cell_height {
+            for cell_x in 0..grid.cell_width {
+                let pixel = if cell_x < glyph_width && cell_y < glyph_height {
+                    glyph.get_pixel(cell_x, cell_y)
+                } else {
+                    Rgba([0, 0, 0, 0])
+                };
+                self.image.put_pixel(x + cell_x, y + cell_y, pixel);
+            }
+        }
+        Ok(())
+    }
+}
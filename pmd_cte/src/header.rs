@@ -0,0 +1,137 @@
+#[cfg(feature = "std")]
+use crate::reader::{IoResultExt, PositionTracker};
+use crate::{CteDecodeError, CteFormat};
+#[cfg(feature = "std")]
+use byteorder::{ReadBytesExt, LE};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+pub(crate) const CTE_HEADER_SIZE: u8 = 28;
+pub(crate) const CTE_HEADER: [u8; 4] = [0x0, 0x63, 0x74, 0x65];
+
+// the offset of each field of the header
+pub(crate) const FORMAT_ID_OFFSET: u64 = 4;
+pub(crate) const WIDTH_OFFSET: u64 = 8;
+pub(crate) const HEIGHT_OFFSET: u64 = 12;
+pub(crate) const PIXEL_LENGTH_OFFSET: u64 = 16;
+pub(crate) const PIXEL_START_OFFSET_OFFSET: u64 = 24;
+
+/// The 28 bytes header at the start of a cte file (excluding the magic)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CteHeader {
+    pub format_id: u32,
+    pub width: u32,
+    pub height: u32,
+    /// the number of bit per pixel
+    pub pixel_length: u32,
+    /// an unknown value
+    pub unk: u32,
+    /// the offset of the pixel data, from the start of the file
+    pub pixel_start_offset: u32,
+}
+
+impl CteHeader {
+    /// the built-in format this header refer to, if it is known
+    pub fn format(&self) -> Option<CteFormat> {
+        CteFormat::from_id(self.format_id)
+    }
+
+    /// the offset, from the start of the file, of the stored tile containing the pixel at the
+    /// given coordinate, for tiles of `tile_size` bytes. Return None if it is outside the image.
+    pub fn tile_offset(&self, tile_size: usize, x: u32, y: u32) -> Option<u64> {
+        if x >= self.width || y >= self.height {
+            return None;
+        };
+        // the last row and column of tiles may be partial, in lenient mode
+        let width_section = self.width.div_ceil(8) as u64;
+        let height_section = self.height.div_ceil(8) as u64;
+        let row = height_section.checked_sub(1 + (y / 8) as u64)?;
+        let tile_index = row * width_section + (x / 8) as u64;
+        Some(self.pixel_start_offset as u64 + tile_index * tile_size as u64)
+    }
+
+    /// parse the header at the start of `data`, including the magic. Only the magic is checked.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CteDecodeError> {
+        if data.len() < CTE_HEADER_SIZE as usize {
+            return Err(CteDecodeError::UnexpectedEnd(data.len() as u64));
+        };
+        if data[0..4] != CTE_HEADER {
+            return Err(CteDecodeError::InvalideHeader(
+                [data[0], data[1], data[2], data[3]],
+                0,
+            ));
+        };
+        let field = |offset: usize| {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+        Ok(Self {
+            format_id: field(4),
+            width: field(8),
+            height: field(12),
+            pixel_length: field(16),
+            unk: field(20),
+            pixel_start_offset: field(24),
+        })
+    }
+
+    /// the header as stored in a file, including the magic
+    pub fn to_bytes(&self) -> [u8; CTE_HEADER_SIZE as usize] {
+        let mut result = [0; CTE_HEADER_SIZE as usize];
+        result[0..4].copy_from_slice(&CTE_HEADER);
+        for (index, value) in [
+            self.format_id,
+            self.width,
+            self.height,
+            self.pixel_length,
+            self.unk,
+            self.pixel_start_offset,
+        ]
+        .iter()
+        .enumerate()
+        {
+            result[4 + index * 4..8 + index * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        result
+    }
+
+    /// read the header, including the magic. Only the magic is checked.
+    #[cfg(feature = "std")]
+    pub(crate) fn read<R: Read>(input: &mut R) -> Result<Self, CteDecodeError> {
+        let mut input = PositionTracker::new(input);
+        let mut header_buffer = [0; 4];
+        input.read_exact(&mut header_buffer).at(input.position)?;
+        if header_buffer != CTE_HEADER {
+            return Err(CteDecodeError::InvalideHeader(header_buffer, 0));
+        };
+        Ok(Self {
+            format_id: input.read_u32::<LE>().at(input.position)?,
+            width: input.read_u32::<LE>().at(input.position)?,
+            height: input.read_u32::<LE>().at(input.position)?,
+            pixel_length: input.read_u32::<LE>().at(input.position)?,
+            unk: input.read_u32::<LE>().at(input.position)?,
+            pixel_start_offset: input.read_u32::<LE>().at(input.position)?,
+        })
+    }
+
+    /// write the header, including the magic, followed by `padding_fill` up to the start of the
+    /// pixel data, in a single write
+    #[cfg(feature = "std")]
+    pub(crate) fn write_with_padding<W: Write>(
+        &self,
+        out: &mut W,
+        padding_fill: u8,
+    ) -> io::Result<()> {
+        let mut data = self.to_bytes().to_vec();
+        data.resize(
+            self.pixel_start_offset.max(CTE_HEADER_SIZE as u32) as usize,
+            padding_fill,
+        );
+        out.write_all(&data)
+    }
+}
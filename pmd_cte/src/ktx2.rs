@@ -0,0 +1,210 @@
+//! Export to the KTX2 texture container, which can be opened by most GPU texture viewers
+
+use crate::etc1::{encode_block, Etc1Quality};
+use crate::{CteFormat, CteImage};
+use image::{DynamicImage, GenericImageView};
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+const VK_FORMAT_ETC2_R8G8B8_SRGB_BLOCK: u32 = 148;
+
+/// the data format descriptor values of the Khronos data format specification
+const KHR_DF_MODEL_RGBSDA: u8 = 1;
+const KHR_DF_MODEL_ETC2: u8 = 161;
+const KHR_DF_PRIMARIES_BT709: u8 = 1;
+const KHR_DF_TRANSFER_SRGB: u8 = 2;
+const KHR_DF_CHANNEL_ALPHA: u8 = 15;
+const KHR_DF_CHANNEL_ETC2_COLOR: u8 = 2;
+const KHR_DF_SAMPLE_DATATYPE_LINEAR: u8 = 0x10;
+
+/// a sample of the data format descriptor, of `bit_length` bits at `bit_offset`
+struct Sample {
+    bit_offset: u16,
+    bit_length: u8,
+    channel: u8,
+    upper: u32,
+}
+
+/// How the pixels of each level are stored
+#[derive(Clone, Copy)]
+enum Ktx2Format {
+    Rgba8,
+    Etc1,
+}
+
+impl Ktx2Format {
+    fn vk_format(self) -> u32 {
+        match self {
+            Self::Rgba8 => VK_FORMAT_R8G8B8A8_SRGB,
+            Self::Etc1 => VK_FORMAT_ETC2_R8G8B8_SRGB_BLOCK,
+        }
+    }
+
+    /// the size of a texel block, in bytes
+    fn block_size(self) -> usize {
+        match self {
+            Self::Rgba8 => 4,
+            Self::Etc1 => 8,
+        }
+    }
+
+    fn data_format_descriptor(self) -> Vec<u8> {
+        let (model, block_dimension, samples) = match self {
+            Self::Rgba8 => (
+                KHR_DF_MODEL_RGBSDA,
+                [0, 0, 0, 0],
+                (0..4)
+                    .map(|channel| Sample {
+                        bit_offset: channel as u16 * 8,
+                        bit_length: 8,
+                        channel: if channel == 3 {
+                            KHR_DF_CHANNEL_ALPHA | KHR_DF_SAMPLE_DATATYPE_LINEAR
+                        } else {
+                            channel
+                        },
+                        upper: 255,
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            Self::Etc1 => (
+                KHR_DF_MODEL_ETC2,
+                [3, 3, 0, 0],
+                vec![Sample {
+                    bit_offset: 0,
+                    bit_length: 64,
+                    channel: KHR_DF_CHANNEL_ETC2_COLOR,
+                    upper: u32::MAX,
+                }],
+            ),
+        };
+        let block_size = 24 + 16 * samples.len();
+        let mut result = Vec::with_capacity(4 + block_size);
+        result.extend_from_slice(&((4 + block_size) as u32).to_le_bytes());
+        // the vendor and type of the basic descriptor block are both 0
+        result.extend_from_slice(&0u32.to_le_bytes());
+        result.extend_from_slice(&2u16.to_le_bytes());
+        result.extend_from_slice(&(block_size as u16).to_le_bytes());
+        result.extend_from_slice(&[model, KHR_DF_PRIMARIES_BT709, KHR_DF_TRANSFER_SRGB, 0]);
+        result.extend_from_slice(&block_dimension);
+        result.extend_from_slice(&[self.block_size() as u8, 0, 0, 0, 0, 0, 0, 0]);
+        for sample in samples {
+            result.extend_from_slice(&sample.bit_offset.to_le_bytes());
+            result.extend_from_slice(&[sample.bit_length - 1, sample.channel, 0, 0, 0, 0]);
+            result.extend_from_slice(&0u32.to_le_bytes());
+            result.extend_from_slice(&sample.upper.to_le_bytes());
+        }
+        result
+    }
+
+    fn encode_level(self, image: &DynamicImage) -> Vec<u8> {
+        match self {
+            Self::Rgba8 => image.to_rgba8().into_raw(),
+            Self::Etc1 => encode_etc1_blocks(image),
+        }
+    }
+}
+
+/// compress the image to ETC1 blocks, row by row, each one stored as a big endian integer like
+/// in the standard layout (contrary to the 3DS one). Blocks overflowing the image repeat its edge.
+fn encode_etc1_blocks(image: &DynamicImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+    let mut result = Vec::with_capacity((blocks_x * blocks_y) as usize * 8);
+    for block_y in 0..blocks_y {
+        for block_x in 0..blocks_x {
+            let mut pixels = [[0; 3]; 16];
+            for x in 0..4 {
+                for y in 0..4 {
+                    let pixel = image.get_pixel(
+                        (block_x * 4 + x).min(width - 1),
+                        (block_y * 4 + y).min(height - 1),
+                    );
+                    pixels[(x * 4 + y) as usize] = [pixel[0], pixel[1], pixel[2]];
+                }
+            }
+            result.extend_from_slice(&encode_block(&pixels, Etc1Quality::High).to_be_bytes());
+        }
+    }
+    result
+}
+
+/// pad `data` with zeroes to a multiple of `alignment`
+fn align(data: &mut Vec<u8>, alignment: usize) {
+    data.resize(data.len().div_ceil(alignment) * alignment, 0);
+}
+
+impl CteImage {
+    /// write the image and its mipmaps in a KTX2 container. ETC1 images are compressed again
+    /// (from their decoded pixels) to the ETC2 format, which ETC1 is a subset of. The other
+    /// formats are stored as rgba.
+    pub fn to_ktx2(&self) -> Vec<u8> {
+        let format = match self.original_format {
+            CteFormat::Etc1 => Ktx2Format::Etc1,
+            _ => Ktx2Format::Rgba8,
+        };
+        let levels: Vec<Vec<u8>> = std::iter::once(&self.image)
+            .chain(self.mipmaps.iter())
+            .map(|level| format.encode_level(level))
+            .collect();
+        let (width, height) = self.image.dimensions();
+
+        let level_index_start = 80;
+        let dfd = format.data_format_descriptor();
+        let dfd_start = level_index_start + 24 * levels.len();
+        let mut key_values = Vec::new();
+        let writer = format!("KTXwriter\0pmd_cte {}\0", env!("CARGO_PKG_VERSION"));
+        key_values.extend_from_slice(&(writer.len() as u32).to_le_bytes());
+        key_values.extend_from_slice(writer.as_bytes());
+        align(&mut key_values, 4);
+        let key_values_start = dfd_start + dfd.len();
+
+        let mut result = Vec::new();
+        result.extend_from_slice(&KTX2_IDENTIFIER);
+        for value in [
+            format.vk_format(),
+            // the size of the data type used for the endianness conversion
+            1,
+            width,
+            height,
+            0,
+            0,
+            1,
+            levels.len() as u32,
+            0,
+        ] {
+            result.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [
+            dfd_start as u32,
+            dfd.len() as u32,
+            key_values_start as u32,
+            key_values.len() as u32,
+        ] {
+            result.extend_from_slice(&value.to_le_bytes());
+        }
+        // there is no supercompression global data
+        result.extend_from_slice(&[0; 16]);
+        result.resize(dfd_start, 0);
+        result.extend_from_slice(&dfd);
+        result.extend_from_slice(&key_values);
+
+        // the levels are stored from the smallest to the biggest
+        for (level_nb, level) in levels.iter().enumerate().rev() {
+            align(&mut result, format.block_size().max(4));
+            let offset = result.len() as u64;
+            let index_offset = level_index_start + 24 * level_nb;
+            for (position, value) in [offset, level.len() as u64, level.len() as u64]
+                .iter()
+                .enumerate()
+            {
+                result[index_offset + position * 8..index_offset + (position + 1) * 8]
+                    .copy_from_slice(&value.to_le_bytes());
+            }
+            result.extend_from_slice(level);
+        }
+        result
+    }
+}
@@ -0,0 +1,112 @@
+use crate::reader::{IoResultExt, PositionTracker};
+use crate::swizzle::tile_to_linear;
+use crate::{decode_tile_pixels, CteDecodeError, CteFormat, CteHeader, CteImage, DecodeOptions};
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+/// A cte file whose header is read when it is opened, but whose tiles are only read and decoded
+/// when a region containing them is requested. Decoded tiles are kept, so each one is read once.
+pub struct LazyCteImage<R> {
+    input: R,
+    /// the position of the start of the file in `input`
+    start: u64,
+    header: CteHeader,
+    format: CteFormat,
+    options: DecodeOptions,
+    /// the decoded tiles, by the coordinate of their top-left pixel
+    tiles: HashMap<(u32, u32), [[u8; 4]; 64]>,
+}
+
+impl<R: Read + Seek> LazyCteImage<R> {
+    /// read the header of the cte file starting at the current position of `input`
+    pub fn new(input: R) -> Result<Self, CteDecodeError> {
+        Self::with_options(input, &DecodeOptions::default())
+    }
+
+    pub fn with_options(mut input: R, options: &DecodeOptions) -> Result<Self, CteDecodeError> {
+        let start = input.stream_position().at(0)?;
+        let (header, format, _) =
+            CteImage::read_checked_header(&mut PositionTracker::new(&mut input), options)?;
+        Ok(Self {
+            input,
+            start,
+            header,
+            format,
+            options: options.clone(),
+            tiles: HashMap::new(),
+        })
+    }
+
+    pub fn header(&self) -> &CteHeader {
+        &self.header
+    }
+
+    pub fn format(&self) -> CteFormat {
+        self.format
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.header.width, self.header.height)
+    }
+
+    /// the tile containing the pixel at the given coordinate, as rgba pixels row by row. It is
+    /// read and decoded the first time it is requested.
+    pub fn tile(&mut self, x: u32, y: u32) -> Result<Option<[[u8; 4]; 64]>, CteDecodeError> {
+        let tile_size = self.format.get_tile_size();
+        let offset = match self.header.tile_offset(tile_size, x, y) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+        let key = (x / 8 * 8, y / 8 * 8);
+        if let Some(tile) = self.tiles.get(&key) {
+            return Ok(Some(*tile));
+        };
+        self.input
+            .seek(SeekFrom::Start(self.start + offset))
+            .at(offset)?;
+        let mut data = vec![0; tile_size];
+        self.input.read_exact(&mut data).at(offset)?;
+        let tile = tile_to_linear(&decode_tile_pixels(&data, self.format, &self.options));
+        self.tiles.insert(key, tile);
+        Ok(Some(tile))
+    }
+
+    /// decode the given region of the image, reading only the tiles it overlap. The part of the
+    /// region outside of the image is cropped out.
+    pub fn decode_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<RgbaImage, CteDecodeError> {
+        let end_x = x.saturating_add(width).min(self.header.width);
+        let end_y = y.saturating_add(height).min(self.header.height);
+        let mut region = RgbaImage::new(end_x.saturating_sub(x), end_y.saturating_sub(y));
+        for tile_y in (y / 8 * 8..end_y).step_by(8) {
+            for tile_x in (x / 8 * 8..end_x).step_by(8) {
+                let tile = self
+                    .tile(tile_x, tile_y)?
+                    .expect("the tile should be inside the image");
+                for (index, pixel) in tile.iter().enumerate() {
+                    let pixel_x = tile_x + index as u32 % 8;
+                    let pixel_y = tile_y + index as u32 / 8;
+                    if (x..end_x).contains(&pixel_x) && (y..end_y).contains(&pixel_y) {
+                        region.put_pixel(pixel_x - x, pixel_y - y, Rgba(*pixel));
+                    };
+                }
+            }
+        }
+        Ok(region)
+    }
+
+    /// forget the decoded tiles, so they are read again when requested
+    pub fn clear_cache(&mut self) {
+        self.tiles.clear();
+    }
+
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
@@ -1,212 +1,879 @@
-use byteorder::{ReadBytesExt, WriteBytesExt, LE};
-use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba};
-use std::io::Read;
-use std::io::{self, Write};
-use thiserror::Error;
-
-#[derive(Error, Debug)]
-pub enum CteDecodeError {
-    #[error("An issue occured when reading the file")]
-    IOError(#[from] io::Error),
-    #[error("the header of the cte file doesn't correspond to the expected one (\\x0cte): {0:?}")]
-    InvalideHeader([u8; 4]),
-    #[error("the cte image format with the id {0} isn't supported")]
-    UnsuportedFormat(u32),
-    #[error("the cte image content is mixed with the header. That shouldn't happen. (the content start at {0})")]
-    ImageStartTooSoon(u32),
-    #[error(
-        "the number of byte by pixel in the file is invalid (the size is {0}, for the format {1:?}"
-    )]
-    PixelLenghtInvalid(u32, CteFormat),
-    #[error("the width {0} of the image isn't a multiple of 8")]
-    WidthNotMultiple8(u32),
-    #[error("the height {0} of the image isn't a multiple of 8")]
-    HeightNotMultiple8(u32),
-    #[error("internal error : {0}")]
-    InternalError(&'static str),
-}
-
-#[derive(Error, Debug)]
-pub enum CteEncodeError {
-    #[error("An issue occured while writing the file")]
-    IOError(#[from] io::Error),
-    #[error("the width {0} of the image isn't a multiple of 8")]
-    WidthNotMultiple8(u32),
-    #[error("the height {0} of the image isn't a multiple of 8")]
-    HeightNotMultiple8(u32),
-}
-
-fn read_in_image_order<B, F>(buffer: &[B; 64], mut func: F)
-where
-    B: Clone,
-    F: FnMut(u32, u32, B),
-{
-    let mut iterator = buffer.iter();
-    for pair1 in &[(0, 4), (4, 4), (0, 0), (4, 0)] {
-        for pair2 in &[(0, 2), (2, 2), (0, 0), (2, 0)] {
-            for pair3 in &[(0, 1), (1, 1), (0, 0), (1, 0)] {
-                let x = pair1.0 + pair2.0 + pair3.0;
-                let y = pair1.1 + pair2.1 + pair3.1;
-                func(x, y, iterator.next().unwrap().clone());
-            }
-        }
-    }
-}
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use image::imageops::FilterType;
+#[cfg(feature = "std")]
+use image::{ColorType, DynamicImage, GenericImageView, ImageBuffer};
+use log::{debug, trace, warn};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod clim;
+#[cfg(feature = "std")]
+pub use builder::CteImageBuilder;
+#[cfg(feature = "std")]
+pub use clim::{clim_to_cte, ClimContainer};
+#[cfg(feature = "std")]
+mod decoder;
+#[cfg(feature = "std")]
+pub use decoder::CteDecoder;
+#[cfg(feature = "std")]
+mod encoder;
+#[cfg(feature = "std")]
+pub use encoder::CteEncoder;
+#[cfg(feature = "file")]
+mod file;
+#[cfg(feature = "file")]
+pub use file::{open, save};
+#[cfg(feature = "std")]
+mod glyph;
+#[cfg(feature = "std")]
+pub use glyph::GlyphGrid;
+pub mod codec;
+pub use codec::CteFormatCodec;
+#[cfg(all(feature = "serde", feature = "std"))]
+mod debug_json;
+mod dither;
+mod error;
+#[cfg(feature = "farc")]
+pub use error::CteFarcError;
+#[cfg(feature = "std")]
+pub use error::CteGlyphError;
+pub use error::{CteDecodeError, CteDecodeWarning, CteEncodeError, CteError, ParseCteFormatError};
+mod etc1;
+#[cfg(feature = "farc")]
+pub mod farc;
+pub use etc1::Etc1Quality;
+mod format;
+mod gamma;
+pub use format::CteFormat;
+mod header;
+pub use header::CteHeader;
+use header::{
+    CTE_HEADER_SIZE, FORMAT_ID_OFFSET, HEIGHT_OFFSET, PIXEL_LENGTH_OFFSET,
+    PIXEL_START_OFFSET_OFFSET, WIDTH_OFFSET,
+};
+#[cfg(feature = "std")]
+mod ktx2;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+pub use lazy::LazyCteImage;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+pub use metrics::{QualityMetrics, DEFAULT_MIN_PSNR};
+mod options;
+pub use options::{DecodeOptions, Dithering, EncodeOptions, LumaConversion, MipmapGeneration};
+#[cfg(feature = "std")]
+mod pixels;
+#[cfg(feature = "std")]
+pub use pixels::DecodedPixels;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+use reader::{IoResultExt, PositionTracker};
+mod simd;
+#[cfg(feature = "sir0")]
+pub mod sir0;
+mod slice;
+pub use slice::{decode_rgba8, encode_rgba8};
+pub mod swizzle;
+use swizzle::{read_in_image_order, tile_coordinate};
+#[cfg(feature = "std")]
+mod tiles;
+#[cfg(feature = "std")]
+pub use tiles::{CteRawTile, CteTile, CteTileReader, CteTileWriter};
+#[cfg(feature = "std")]
+mod validate;
+mod view;
+#[cfg(feature = "std")]
+pub use validate::{ValidationFinding, ValidationReport};
+pub use view::CteView;
 
-#[derive(Debug)]
-pub enum CteFormat {
-    A8,
+#[cfg(feature = "std")]
+pub struct CteImage {
+    pub original_format: CteFormat,
+    pub image: DynamicImage,
+    /// the header of the file this image was decoded from, if any
+    pub header: Option<CteHeader>,
+    /// the problems that were ignored while decoding this image in lenient mode
+    pub decode_warnings: Vec<CteDecodeWarning>,
+    /// the additional mipmap levels following the main image, each one half the size of the
+    /// previous one
+    pub mipmaps: Vec<DynamicImage>,
+    /// the layout of the glyphs, for font files. It isn't stored in the file.
+    pub glyph_grid: Option<GlyphGrid>,
 }
 
-impl CteFormat {
-    pub fn from_id(id: u32) -> Option<Self> {
-        Some(match id {
-            8 => Self::A8,
-            _ => return None,
-        })
+#[cfg(feature = "std")]
+impl CteImage {
+    pub fn new(original_format: CteFormat, image: DynamicImage) -> Self {
+        Self {
+            original_format,
+            image,
+            header: None,
+            decode_warnings: Vec::new(),
+            mipmaps: Vec::new(),
+            glyph_grid: None,
+        }
     }
 
-    pub fn get_id(&self) -> u32 {
-        match self {
-            Self::A8 => 8,
-        }
+    /// the dimensions of the image itself, which may be smaller than the stored one
+    pub fn logical_dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
     }
 
-    pub fn check_pixel_lenght_bit(&self, lenght: u32) -> bool {
-        lenght == self.get_pixel_length_bit()
+    /// the pixels of the image, with only the channels stored by its format
+    pub fn decoded_pixels(&self) -> DecodedPixels {
+        let alpha_only = matches!(self.original_format, CteFormat::A8 | CteFormat::A4);
+        DecodedPixels::from_image(&self.image, alpha_only)
     }
 
-    pub fn get_pixel_length_bit(&self) -> u32 {
-        match self {
-            Self::A8 => 8,
-        }
+    /// the dimensions of the image once stored, rounded up to a multiple of 8
+    pub fn stored_dimensions(&self) -> (u32, u32) {
+        let round_up = |value: u32| value.div_ceil(8) * 8;
+        (round_up(self.image.width()), round_up(self.image.height()))
     }
-}
 
-const CTE_HEADER_SIZE: u8 = 28;
-const CTE_HEADER: [u8; 4] = [0x0, 0x63, 0x74, 0x65];
+    /// start building an image, that will be checked to be encodable in the given format
+    pub fn builder(image: DynamicImage, format: CteFormat) -> CteImageBuilder {
+        CteImageBuilder::new(image, format)
+    }
 
-pub struct CteImage {
-    pub original_format: CteFormat,
-    pub image: DynamicImage,
-}
+    /// read only the header of a cte file, without checking it is supported nor decoding the pixels
+    pub fn decode_header<R: Read>(input: &mut R) -> Result<CteHeader, CteDecodeError> {
+        CteHeader::read(input)
+    }
 
-impl CteImage {
     pub fn decode_cte<R: Read>(input: &mut R) -> Result<CteImage, CteDecodeError> {
-        let mut header_buffer = [0; 4];
-        input.read_exact(&mut header_buffer)?;
-        if header_buffer != CTE_HEADER {
-            return Err(CteDecodeError::InvalideHeader(header_buffer));
-        };
-        let format_id = input.read_u32::<LE>()?;
-        let image_format = if let Some(f) = CteFormat::from_id(format_id) {
-            f
-        } else {
-            return Err(CteDecodeError::UnsuportedFormat(format_id));
-        };
+        Self::decode_cte_with(input, &DecodeOptions::default())
+    }
 
-        let width = input.read_u32::<LE>()?;
-        let height = input.read_u32::<LE>()?;
-        let pixel_lenght = input.read_u32::<LE>()?;
-        let _unk = input.read_u32::<LE>()?;
-        let pixel_start_offset = input.read_u32::<LE>()?;
+    /// decode the image, using one of the given codecs if it match the format id of the file
+    /// (prefering them over the built-in ones).
+    pub fn decode_cte_with_codecs<R: Read>(
+        input: &mut R,
+        codecs: &[&'static dyn CteFormatCodec],
+    ) -> Result<CteImage, CteDecodeError> {
+        Self::decode_cte_with(
+            input,
+            &DecodeOptions {
+                codecs: codecs.to_vec(),
+                ..DecodeOptions::default()
+            },
+        )
+    }
 
-        if !image_format.check_pixel_lenght_bit(pixel_lenght) {
-            return Err(CteDecodeError::PixelLenghtInvalid(
-                pixel_lenght,
-                image_format,
+    pub fn decode_cte_with<R: Read>(
+        input: &mut R,
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteDecodeError> {
+        let mut input = PositionTracker::new(input);
+        let (header, image_format, warnings) = Self::read_checked_header(&mut input, options)?;
+        // in lenient mode, pixel data inside the header are considered to start just after it
+        let distance_before_start = header
+            .pixel_start_offset
+            .saturating_sub(CTE_HEADER_SIZE as u32);
+        input.skip(distance_before_start as u64)?;
+        Self::decode_pixels(&mut input, header, image_format, warnings, options)
+    }
+
+    /// decode the pixels of the image as rgba, row by row, into a buffer provided by the caller,
+    /// to avoid allocating a new one for each image. Mipmaps are ignored.
+    ///
+    /// `buffer` should be at least `width * height * 4` bytes long, according to the returned header.
+    pub fn decode_cte_into<R: Read>(
+        input: &mut R,
+        buffer: &mut [u8],
+        options: &DecodeOptions,
+    ) -> Result<CteHeader, CteDecodeError> {
+        let mut input = PositionTracker::new(input);
+        let (header, image_format, _) = Self::read_checked_header(&mut input, options)?;
+        let needed = (header.width as usize)
+            .saturating_mul(header.height as usize)
+            .saturating_mul(4);
+        if buffer.len() < needed {
+            return Err(CteDecodeError::BufferTooSmall(
+                buffer.len(),
+                needed,
+                input.position,
             ));
         };
+        let distance_before_start = header
+            .pixel_start_offset
+            .saturating_sub(CTE_HEADER_SIZE as u32);
+        input.skip(distance_before_start as u64)?;
+        decode_level_into(
+            &mut input,
+            header.width,
+            header.height,
+            image_format,
+            &mut buffer[..needed],
+            options,
+        )?;
+        Ok(header)
+    }
 
-        let distance_before_start = pixel_start_offset
-            .checked_sub(CTE_HEADER_SIZE as u32)
-            .map_or_else(
-                || Err(CteDecodeError::ImageStartTooSoon(pixel_start_offset)),
-                Ok,
-            )?;
-        input.read_exact(&mut vec![0; distance_before_start as usize])?;
+    /// decode the image, seeking directly to the pixel data instead of reading what is before it.
+    /// The header should start at the current position of `input`.
+    pub fn decode_cte_seek<R: Read + Seek>(input: &mut R) -> Result<CteImage, CteDecodeError> {
+        Self::decode_cte_seek_with(input, &DecodeOptions::default())
+    }
 
-        if width % 8 != 0 {
-            return Err(CteDecodeError::WidthNotMultiple8(width));
-        };
-        if height % 8 != 0 {
-            return Err(CteDecodeError::HeightNotMultiple8(height));
-        };
-        let width_section = width / 8;
-        let height_section = height / 8;
-        let image = match image_format {
-            CteFormat::A8 => {
-                let mut image: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
-                for y in (0..height_section).rev() {
-                    for x in 0..width_section {
-                        let mut section = [0; 64];
-                        input.read_exact(&mut section)?;
-                        let start_x = x * 8;
-                        let start_y = y * 8;
-                        let image_ref = &mut image;
-                        read_in_image_order(&section, move |x, y, v| {
-                            let alpha = (v % 16) * 16;
-                            let white = v / 16;
-                            image_ref.put_pixel(
-                                start_x + x,
-                                start_y + y,
-                                Rgba([white, white, white, alpha]),
-                            )
-                        });
-                    }
-                }
-                DynamicImage::ImageRgba8(image)
+    pub fn decode_cte_seek_with<R: Read + Seek>(
+        input: &mut R,
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteDecodeError> {
+        let start = input.stream_position().at(0)?;
+        let mut input = PositionTracker::new(input);
+        let (header, image_format, warnings) = Self::read_checked_header(&mut input, options)?;
+        // like decode_cte_with, pixel data inside the header start just after it
+        let pixel_start = header.pixel_start_offset.max(CTE_HEADER_SIZE as u32) as u64;
+        input
+            .inner
+            .seek(SeekFrom::Start(start + pixel_start))
+            .at(input.position)?;
+        input.position = pixel_start;
+        Self::decode_pixels(&mut input, header, image_format, warnings, options)
+    }
+
+    /// read the header, and check the image it describe can be decoded
+    fn read_checked_header<R: Read>(
+        input: &mut R,
+        options: &DecodeOptions,
+    ) -> Result<(CteHeader, CteFormat, Vec<CteDecodeWarning>), CteDecodeError> {
+        let header = Self::decode_header(input)?;
+        let (image_format, warnings) = check_header(&header, options)?;
+        Ok((header, image_format, warnings))
+    }
+
+    /// decode the pixel data, which should start at the current position of `input`
+    fn decode_pixels<R: Read>(
+        input: &mut PositionTracker<R>,
+        header: CteHeader,
+        image_format: CteFormat,
+        warnings: Vec<CteDecodeWarning>,
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteDecodeError> {
+        let width = header.width;
+        let height = header.height;
+        let codec = image_format.codec();
+        let image = decode_level(input, width, height, image_format, options)?;
+
+        let mut mipmaps = Vec::new();
+        let mut warnings = warnings;
+        if options.mipmaps {
+            let rest_start = input.position;
+            let mut rest = Vec::new();
+            input.read_to_end(&mut rest).at(input.position)?;
+            let rest_end = input.position;
+            let mut rest = PositionTracker {
+                inner: Cursor::new(rest),
+                position: rest_start,
+            };
+            for (level_width, level_height) in mipmap_dimensions(width, height) {
+                let level_size = level_byte_size(level_width, level_height, codec);
+                if rest_end - rest.position < level_size {
+                    break;
+                };
+                mipmaps.push(decode_level(
+                    &mut rest,
+                    level_width,
+                    level_height,
+                    image_format,
+                    options,
+                )?);
             }
+            debug!("decoded {} mipmap levels", mipmaps.len());
+            let trailing = rest_end - rest.position;
+            if trailing > 0 {
+                let warning = CteDecodeWarning::TrailingData(trailing);
+                warn!("{}", warning);
+                warnings.push(warning);
+            };
+        };
+
+        let image = if let Some((logical_width, logical_height)) = options.logical_size {
+            image.crop_imm(0, 0, logical_width.min(width), logical_height.min(height))
+        } else {
+            image
         };
         Ok(CteImage {
             image,
             original_format: image_format,
+            header: Some(header),
+            decode_warnings: warnings,
+            mipmaps,
+            glyph_grid: None,
         })
     }
 
+    /// decode an image stored in memory
+    pub fn decode_from_slice(data: &[u8]) -> Result<CteImage, CteDecodeError> {
+        Self::decode_cte_seek(&mut Cursor::new(data))
+    }
+
+    /// encode the image into a newly allocated buffer
+    pub fn encode_to_vec(&self) -> Result<Vec<u8>, CteEncodeError> {
+        let (stored_width, stored_height) = self.stored_dimensions();
+        let tile_count = (stored_width as usize / 8) * (stored_height as usize / 8);
+        let mut result =
+            Vec::with_capacity(128 + tile_count * self.original_format.get_tile_size());
+        self.encode_cte(&mut result)?;
+        Ok(result)
+    }
+
+    /// encode the image to `out`. The header is written at once, then each tile with a single
+    /// write, so `out` doesn't need to be buffered.
     pub fn encode_cte<W: Write>(&self, out: &mut W) -> Result<(), CteEncodeError> {
-        out.write_all(&CTE_HEADER)?;
-        out.write_u32::<LE>(self.original_format.get_id())?;
-        out.write_u32::<LE>(self.image.width())?;
-        out.write_u32::<LE>(self.image.height())?;
-        out.write_u32::<LE>(self.original_format.get_pixel_length_bit())?;
-        out.write_u32::<LE>(0)?;
-        out.write_u32::<LE>(128)?;
-        let padding = [0; 128 - (CTE_HEADER_SIZE as usize)];
-        out.write_all(&padding)?;
-        if self.image.width() % 8 != 0 {
+        self.encode_cte_with(out, &EncodeOptions::default())
+    }
+
+    /// encode the image to `out`, like [`CteImage::encode_cte`], with the given options
+    pub fn encode_cte_with<W: Write>(
+        &self,
+        out: &mut W,
+        options: &EncodeOptions,
+    ) -> Result<(), CteEncodeError> {
+        let format = match options.auto_format {
+            Some(min_psnr) => CteFormat::choose_for_with(&self.image, min_psnr),
+            None => options.format.unwrap_or(self.original_format),
+        };
+        if !options.pad && !self.image.width().is_multiple_of(8) {
             return Err(CteEncodeError::WidthNotMultiple8(self.image.width()));
         };
-        if self.image.height() % 8 != 0 {
+        if !options.pad && !self.image.height().is_multiple_of(8) {
             return Err(CteEncodeError::HeightNotMultiple8(self.image.height()));
         };
-        let height_section = self.image.height() / 8;
-        let width_section = self.image.width() / 8;
-        for y_base in (0..height_section).rev() {
-            for x_base in 0..width_section {
-                let x_base = x_base * 8;
-                let y_base = y_base * 8;
-                for pair1 in &[(0, 4), (4, 4), (0, 0), (4, 0)] {
-                    for pair2 in &[(0, 2), (2, 2), (0, 0), (2, 0)] {
-                        for pair3 in &[(0, 1), (1, 1), (0, 0), (1, 0)] {
-                            let x_coord = x_base + pair1.0 + pair2.0 + pair3.0;
-                            let y_coord = y_base + pair1.1 + pair2.1 + pair3.1;
-                            match self.original_format {
-                                CteFormat::A8 => {
-                                    let pixel = self.image.get_pixel(x_coord, y_coord).0;
-                                    let white =
-                                        ((pixel[0] as u16 + pixel[1] as u16 + pixel[2] as u16) / 3)
-                                            as u8;
-                                    let alpha = pixel[3];
-                                    let to_write = white.overflowing_shl(4).0 + (alpha / 16);
-                                    out.write_u8(to_write)?; //TODO: find a clean way to handle those colors
-                                }
-                            }
-                        }
-                    }
-                }
+        let (stored_width, stored_height) = self.stored_dimensions();
+        if options.pixel_start_offset < CTE_HEADER_SIZE as u32 {
+            return Err(CteEncodeError::ImageStartTooSoon(
+                options.pixel_start_offset,
+            ));
+        };
+        let header = CteHeader {
+            format_id: format.get_id(),
+            width: stored_width,
+            height: stored_height,
+            pixel_length: format.get_pixel_length_bit(),
+            unk: options
+                .unk
+                .or_else(|| self.header.as_ref().map(|h| h.unk))
+                .unwrap_or(0),
+            pixel_start_offset: options.pixel_start_offset,
+        };
+        debug!("encoding a {:?} image with the header {:?}", format, header);
+        header.write_with_padding(out, options.padding_fill)?;
+        let mut alpha_discarded = false;
+        encode_level(
+            &self.image,
+            (stored_width, stored_height),
+            format,
+            options,
+            out,
+            &mut alpha_discarded,
+        )?;
+        let generated_mipmaps;
+        let mipmaps = match options.mipmaps {
+            MipmapGeneration::Existing => &self.mipmaps,
+            MipmapGeneration::Generate(count) => {
+                generated_mipmaps = self.generate_mipmaps(count);
+                &generated_mipmaps
             }
+        };
+        for (level, mipmap) in mipmaps.iter().enumerate() {
+            let divisor = 2 << level;
+            encode_level(
+                mipmap,
+                (stored_width / divisor, stored_height / divisor),
+                format,
+                options,
+                out,
+                &mut alpha_discarded,
+            )?;
         }
+        if alpha_discarded {
+            warn!(
+                "the image contain transparent pixel, but the {:?} format doesn't store alpha. It has been discarded.",
+                format
+            );
+        };
         Ok(())
     }
+
+    /// convert the image (and its mipmaps) to another format, as if it was encoded then decoded
+    /// again. Also return true if some information was lost in the process.
+    pub fn convert_to(&self, format: CteFormat) -> Result<(CteImage, bool), CteEncodeError> {
+        let options = EncodeOptions {
+            format: Some(format),
+            strict: false,
+            pad: true,
+            ..EncodeOptions::default()
+        };
+        let (image, mut lossy) = convert_level(&self.image, format, &options)?;
+        let mut mipmaps = Vec::new();
+        for mipmap in &self.mipmaps {
+            let (mipmap, mipmap_lossy) = convert_level(mipmap, format, &options)?;
+            lossy |= mipmap_lossy;
+            mipmaps.push(mipmap);
+        }
+        Ok((
+            CteImage {
+                original_format: format,
+                image,
+                header: None,
+                decode_warnings: Vec::new(),
+                mipmaps,
+                glyph_grid: self.glyph_grid,
+            },
+            lossy,
+        ))
+    }
+
+    /// create `count` mipmap levels by downscaling the image, stopping early if a level would have
+    /// a dimension that isn't a multiple of 8
+    pub fn generate_mipmaps(&self, count: u32) -> Vec<DynamicImage> {
+        let (stored_width, stored_height) = self.stored_dimensions();
+        let mut result = Vec::new();
+        let (mut width, mut height) = (stored_width / 2, stored_height / 2);
+        while (result.len() as u32) < count
+            && width % 8 == 0
+            && height % 8 == 0
+            && width > 0
+            && height > 0
+        {
+            result.push(self.image.resize_exact(width, height, FilterType::Triangle));
+            width /= 2;
+            height /= 2;
+        }
+        result
+    }
+}
+
+/// decode a single level of the image, starting at the current position of `input`
+#[cfg(feature = "std")]
+fn decode_level<R: Read>(
+    input: &mut PositionTracker<R>,
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    options: &DecodeOptions,
+) -> Result<DynamicImage, CteDecodeError> {
+    // the pixel data is read first, so the buffer is only allocated if the input is big enough
+    let data = input.read_bytes(level_byte_size(width, height, format.codec()))?;
+    let mut buffer = vec![0; width as usize * height as usize * 4];
+    decode_level_data(width, height, format, &data, &mut buffer, options)?;
+    let position = input.position;
+    let missing = || {
+        CteDecodeError::InternalError(
+            "the decoded buffer doesn't have the expected size",
+            position,
+        )
+    };
+    // the channels are copied instead of using the conversions of the image crate, which compute
+    // the luminance with floating point weights, turning some gray values into the one below
+    let pick = |channels: &[usize]| -> Vec<u8> {
+        buffer
+            .chunks_exact(4)
+            .flat_map(|pixel| channels.iter().map(move |channel| pixel[*channel]))
+            .collect()
+    };
+    Ok(match decoded_color_type(format.codec()) {
+        ColorType::L8 => DynamicImage::ImageLuma8(
+            ImageBuffer::from_raw(width, height, pick(&[0])).ok_or_else(missing)?,
+        ),
+        ColorType::La8 => DynamicImage::ImageLumaA8(
+            ImageBuffer::from_raw(width, height, pick(&[0, 3])).ok_or_else(missing)?,
+        ),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(
+            ImageBuffer::from_raw(width, height, pick(&[0, 1, 2])).ok_or_else(missing)?,
+        ),
+        _ => DynamicImage::ImageRgba8(
+            ImageBuffer::from_raw(width, height, buffer).ok_or_else(missing)?,
+        ),
+    })
+}
+
+/// check the image described by the header can be decoded, returning its format and the problems
+/// ignored in lenient mode
+fn check_header(
+    header: &CteHeader,
+    options: &DecodeOptions,
+) -> Result<(CteFormat, Vec<CteDecodeWarning>), CteDecodeError> {
+    let mut warnings = Vec::new();
+    let format_id = header.format_id;
+    let image_format = if let Some(codec) = options.codecs.iter().find(|c| c.id() == format_id) {
+        CteFormat::Custom(*codec)
+    } else if let Some(f) = CteFormat::from_id(format_id) {
+        f
+    } else {
+        return Err(CteDecodeError::UnsuportedFormat(
+            format_id,
+            FORMAT_ID_OFFSET,
+        ));
+    };
+
+    if !image_format.check_pixel_lenght_bit(header.pixel_length) {
+        if options.strict {
+            return Err(CteDecodeError::PixelLenghtInvalid(
+                header.pixel_length,
+                image_format,
+                PIXEL_LENGTH_OFFSET,
+            ));
+        };
+        warnings.push(CteDecodeWarning::PixelLengthMismatch {
+            found: header.pixel_length,
+            expected: image_format.get_pixel_length_bit(),
+        });
+    };
+
+    if header.pixel_start_offset < CTE_HEADER_SIZE as u32 {
+        if options.strict {
+            return Err(CteDecodeError::ImageStartTooSoon(
+                header.pixel_start_offset,
+                PIXEL_START_OFFSET_OFFSET,
+            ));
+        };
+        warnings.push(CteDecodeWarning::ImageStartTooSoon(
+            header.pixel_start_offset,
+        ));
+    };
+
+    if !header.width.is_multiple_of(8) {
+        return Err(CteDecodeError::WidthNotMultiple8(
+            header.width,
+            WIDTH_OFFSET,
+        ));
+    };
+    if !header.height.is_multiple_of(8) {
+        return Err(CteDecodeError::HeightNotMultiple8(
+            header.height,
+            HEIGHT_OFFSET,
+        ));
+    };
+    debug!(
+        "decoding a {:?} image with the header {:?}",
+        image_format, header
+    );
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+    Ok((image_format, warnings))
+}
+
+/// multiply the color channels of a pixel by its alpha
+fn premultiply(pixel: &mut [u8; 4]) {
+    let alpha = pixel[3] as u32;
+    for channel in &mut pixel[0..3] {
+        *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+    }
+}
+
+/// divide the color channels of a premultiplied pixel by its alpha
+fn unpremultiply(pixel: &mut [u8; 4]) {
+    let alpha = pixel[3] as u32;
+    if alpha == 0 {
+        return;
+    };
+    for channel in &mut pixel[0..3] {
+        *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+    }
+}
+
+/// the dimensions of the mipmaps that can follow an image of the given dimensions, each one half
+/// the size of the previous one, as long as they are a non-zero multiple of 8
+#[cfg(feature = "std")]
+fn mipmap_dimensions(width: u32, height: u32) -> impl Iterator<Item = (u32, u32)> {
+    core::iter::successors(Some((width / 2, height / 2)), |(w, h)| Some((w / 2, h / 2)))
+        .take_while(|(w, h)| w % 8 == 0 && h % 8 == 0 && *w > 0 && *h > 0)
+}
+
+/// the number of bytes used to store a level of the given dimensions
+fn level_byte_size(width: u32, height: u32, codec: &dyn CteFormatCodec) -> u64 {
+    (width as u64 / 8)
+        .saturating_mul(height as u64 / 8)
+        .saturating_mul(codec.tile_size() as u64)
+}
+
+/// encode then decode a single level of the image in the given format, returning it and whether
+/// it changed
+#[cfg(feature = "std")]
+fn convert_level(
+    image: &DynamicImage,
+    format: CteFormat,
+    options: &EncodeOptions,
+) -> Result<(DynamicImage, bool), CteEncodeError> {
+    let (width, height) = image.dimensions();
+    let stored_dimensions = (width.div_ceil(8) * 8, height.div_ceil(8) * 8);
+    let mut encoded = Vec::new();
+    let mut alpha_discarded = false;
+    encode_level(
+        image,
+        stored_dimensions,
+        format,
+        options,
+        &mut encoded,
+        &mut alpha_discarded,
+    )?;
+    let converted = decode_level(
+        &mut PositionTracker::new(Cursor::new(encoded)),
+        stored_dimensions.0,
+        stored_dimensions.1,
+        format,
+        &DecodeOptions {
+            premultiplied_alpha: options.premultiplied_alpha,
+            ..DecodeOptions::default()
+        },
+    )
+    .map_err(CteEncodeError::EncodedImageInvalid)?
+    .crop_imm(0, 0, width, height);
+    let lossy = converted.to_rgba8() != image.to_rgba8();
+    Ok((converted, lossy))
+}
+
+/// decode a single level of the image as rgba pixels, row by row, into `buffer`, which should be
+/// big enough to contain them
+#[cfg(feature = "std")]
+fn decode_level_into<R: Read>(
+    input: &mut PositionTracker<R>,
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    buffer: &mut [u8],
+    options: &DecodeOptions,
+) -> Result<(), CteDecodeError> {
+    let data = input.read_bytes(level_byte_size(width, height, format.codec()))?;
+    decode_level_data(width, height, format, &data, buffer, options)
+}
+
+/// decode the stored data of a level, which should contain all of its tiles, into `buffer`
+#[cfg(feature = "std")]
+fn decode_level_data(
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    data: &[u8],
+    buffer: &mut [u8],
+    options: &DecodeOptions,
+) -> Result<(), CteDecodeError> {
+    #[cfg(feature = "rayon")]
+    {
+        decode_tiles_parallel(width, height, format, data, buffer, options);
+        Ok(())
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        let mut tiles = data.chunks(format.codec().tile_size());
+        decode_tiles(width, height, format, buffer, options, |section| {
+            // the length of the data was checked when reading it
+            if let Some(tile) = tiles.next() {
+                section.copy_from_slice(tile);
+            };
+            Ok(())
+        })
+    }
+}
+
+/// decode the tiles of a level as rgba pixels, row by row, into `buffer`. `next_tile` is called to
+/// fill the stored data of each tile, in the order they are stored in.
+fn decode_tiles<F>(
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    buffer: &mut [u8],
+    options: &DecodeOptions,
+    mut next_tile: F,
+) -> Result<(), CteDecodeError>
+where
+    F: FnMut(&mut [u8]) -> Result<(), CteDecodeError>,
+{
+    let width_section = width / 8;
+    let height_section = height / 8;
+    let mut section = vec![0; format.codec().tile_size()];
+    for y in (0..height_section).rev() {
+        for x in 0..width_section {
+            next_tile(&mut section)?;
+            trace!("decoding the tile at ({}, {}): {:02x?}", x, y, section);
+            let start_x = x * 8;
+            let start_y = y * 8;
+            let pixels = decode_tile_pixels(&section, format, options);
+            read_in_image_order(&pixels, |x, y, v| {
+                let index = ((start_y + y) as usize * width as usize + (start_x + x) as usize) * 4;
+                buffer[index..index + 4].copy_from_slice(&v);
+            });
+        }
+    }
+    Ok(())
+}
+
+/// decode the stored data of a tile to rgba pixels, in the order they are stored in
+fn decode_tile_pixels(section: &[u8], format: CteFormat, options: &DecodeOptions) -> [[u8; 4]; 64] {
+    let mut pixels = [[0; 4]; 64];
+    format.codec().decode_tile(section, &mut pixels);
+    if !options.expand_nibbles_17 {
+        let (color_nibble, alpha_nibble) = format.nibble_channels();
+        for pixel in pixels.iter_mut() {
+            if color_nibble {
+                pixel[0..3].iter_mut().for_each(|v| *v = *v / 17 * 16);
+            };
+            if alpha_nibble {
+                pixel[3] = pixel[3] / 17 * 16;
+            };
+        }
+    };
+    if options.premultiplied_alpha {
+        pixels.iter_mut().for_each(unpremultiply);
+    };
+    pixels
+}
+
+/// decode the stored data of all the tiles of a level as rgba pixels, row by row, into
+/// `buffer`, each row of tiles being decoded in parallel
+#[cfg(feature = "rayon")]
+fn decode_tiles_parallel(
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    data: &[u8],
+    buffer: &mut [u8],
+    options: &DecodeOptions,
+) {
+    let width_section = width as usize / 8;
+    let height_section = height as usize / 8;
+    let tile_size = format.codec().tile_size();
+    let row_size = width as usize * 8 * 4;
+    if row_size == 0 {
+        return;
+    };
+    buffer[..row_size * height_section]
+        .par_chunks_mut(row_size)
+        .enumerate()
+        .for_each(|(y, row)| {
+            // the rows of tiles are stored from the bottom to the top
+            let stored_row = height_section - 1 - y;
+            for x in 0..width_section {
+                let start = (stored_row * width_section + x) * tile_size;
+                let section = &data[start..start + tile_size];
+                trace!("decoding the tile at ({}, {}): {:02x?}", x, y, section);
+                let pixels = decode_tile_pixels(section, format, options);
+                read_in_image_order(&pixels, |tile_x, tile_y, v| {
+                    let index = (tile_y as usize * width as usize + x * 8 + tile_x as usize) * 4;
+                    row[index..index + 4].copy_from_slice(&v);
+                });
+            }
+        });
+}
+
+/// the color type of the images decoded with this codec
+#[cfg(feature = "std")]
+fn decoded_color_type(codec: &dyn CteFormatCodec) -> ColorType {
+    match codec.color_type() {
+        ColorType::L8 => ColorType::L8,
+        ColorType::La8 => ColorType::La8,
+        ColorType::Rgb8 => ColorType::Rgb8,
+        _ => ColorType::Rgba8,
+    }
+}
+
+/// encode a single level of the image, padding it with transparent pixels to `stored_dimensions`
+#[cfg(feature = "std")]
+fn encode_level<W: Write>(
+    image: &DynamicImage,
+    stored_dimensions: (u32, u32),
+    format: CteFormat,
+    options: &EncodeOptions,
+    out: &mut W,
+    alpha_discarded: &mut bool,
+) -> Result<(), CteEncodeError> {
+    encode_tiles(
+        |x, y| {
+            if image.in_bounds(x, y) {
+                image.get_pixel(x, y).0
+            } else {
+                [0, 0, 0, 0]
+            }
+        },
+        stored_dimensions,
+        format,
+        options,
+        alpha_discarded,
+        |tile| Ok(out.write_all(tile)?),
+    )
+}
+
+/// encode the tiles of a level, in the order they are stored in. `pixel_at` return the pixel at
+/// the given coordinate, and `write_tile` is called with the stored data of each tile.
+fn encode_tiles<P, F>(
+    pixel_at: P,
+    stored_dimensions: (u32, u32),
+    format: CteFormat,
+    options: &EncodeOptions,
+    alpha_discarded: &mut bool,
+    mut write_tile: F,
+) -> Result<(), CteEncodeError>
+where
+    P: Fn(u32, u32) -> [u8; 4],
+    F: FnMut(&[u8]) -> Result<(), CteEncodeError>,
+{
+    let codec = format.codec();
+    let pixel_at = |x: u32, y: u32| {
+        let mut pixel = pixel_at(x, y);
+        if let Some(threshold) = options.alpha_threshold {
+            pixel[3] = if pixel[3] >= threshold { 255 } else { 0 };
+        };
+        if options.premultiplied_alpha {
+            premultiply(&mut pixel);
+        };
+        pixel
+    };
+    let dithered = dither::dither(pixel_at, stored_dimensions, format, options);
+    let pixel_at = |x: u32, y: u32| match &dithered {
+        Some(dithered) => dithered[y as usize * stored_dimensions.0 as usize + x as usize],
+        None => pixel_at(x, y),
+    };
+    let width_section = stored_dimensions.0 / 8;
+    let height_section = stored_dimensions.1 / 8;
+    // the tiles of the built-in formats fit on the stack, but those of custom codecs may not
+    let mut stack_tile = [0; 64 * 4];
+    let mut heap_tile = Vec::new();
+    let tile = if codec.tile_size() <= stack_tile.len() {
+        &mut stack_tile[..codec.tile_size()]
+    } else {
+        heap_tile.resize(codec.tile_size(), 0);
+        &mut heap_tile[..]
+    };
+    for y_base in (0..height_section).rev() {
+        for x_base in 0..width_section {
+            let x_base = x_base * 8;
+            let y_base = y_base * 8;
+            let mut pixels = [[0; 4]; 64];
+            for (index, pixel) in pixels.iter_mut().enumerate() {
+                let (x, y) = tile_coordinate(index);
+                *pixel = pixel_at(x_base + x, y_base + y);
+            }
+            if !codec.has_alpha() && pixels.iter().any(|p| p[3] != 255) {
+                if options.strict {
+                    return Err(CteEncodeError::AlphaDiscarded(format));
+                };
+                *alpha_discarded = true;
+            };
+            codec.encode_tile(&pixels, options, tile);
+            write_tile(tile)?;
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,178 @@
+use crate::{CteEncodeError, CteFormat, CteImage, DecodeOptions, EncodeOptions, LumaConversion};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use std::io::Write;
+
+/// How much an image differ from the source it was encoded from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityMetrics {
+    /// the mean squared error, over all the channels (including alpha)
+    pub mse: f64,
+    /// the peak signal-to-noise ratio, in dB, over all the channels. Infinite for identical images.
+    pub psnr: f64,
+    /// the mean structural similarity of the luminance, computed on 8×8 windows. 1 for identical
+    /// images.
+    pub ssim: f64,
+    /// the biggest difference of a single channel of a pixel
+    pub max_error: u8,
+}
+
+impl QualityMetrics {
+    /// compare two images. Only the part of `result` that overlap `source` is compared.
+    pub fn compare(source: &DynamicImage, result: &DynamicImage) -> Self {
+        let source = source.to_rgba8();
+        let result = result.to_rgba8();
+        let (width, height) = (
+            source.width().min(result.width()),
+            source.height().min(result.height()),
+        );
+
+        let mut squared_sum = 0u64;
+        let mut max_error = 0;
+        for y in 0..height {
+            for x in 0..width {
+                let (a, b) = (source.get_pixel(x, y).0, result.get_pixel(x, y).0);
+                for channel in 0..4 {
+                    let error = (a[channel] as i32 - b[channel] as i32).unsigned_abs();
+                    squared_sum += (error * error) as u64;
+                    max_error = max_error.max(error as u8);
+                }
+            }
+        }
+        let sample_count = (width as u64 * height as u64 * 4).max(1);
+        let mse = squared_sum as f64 / sample_count as f64;
+        let psnr = if mse == 0.0 {
+            f64::INFINITY
+        } else {
+            10.0 * (255.0 * 255.0 / mse).log10()
+        };
+
+        Self {
+            mse,
+            psnr,
+            ssim: ssim(&source, &result, width, height),
+            max_error,
+        }
+    }
+}
+
+/// the mean SSIM of the luminance of the 8×8 windows of the images
+fn ssim(source: &RgbaImage, result: &RgbaImage, width: u32, height: u32) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let luma = |image: &RgbaImage, x, y| LumaConversion::Rec601.convert(image.get_pixel(x, y).0);
+    let mut total = 0.0;
+    let mut window_count = 0;
+    for window_y in (0..height).step_by(8) {
+        for window_x in (0..width).step_by(8) {
+            let mut values = Vec::new();
+            for y in window_y..(window_y + 8).min(height) {
+                for x in window_x..(window_x + 8).min(width) {
+                    values.push((luma(source, x, y) as f64, luma(result, x, y) as f64));
+                }
+            }
+            let count = values.len() as f64;
+            let mean_a = values.iter().map(|v| v.0).sum::<f64>() / count;
+            let mean_b = values.iter().map(|v| v.1).sum::<f64>() / count;
+            let mut variance_a = 0.0;
+            let mut variance_b = 0.0;
+            let mut covariance = 0.0;
+            for (a, b) in &values {
+                variance_a += (a - mean_a) * (a - mean_a);
+                variance_b += (b - mean_b) * (b - mean_b);
+                covariance += (a - mean_a) * (b - mean_b);
+            }
+            variance_a /= count;
+            variance_b /= count;
+            covariance /= count;
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            window_count += 1;
+        }
+    }
+    if window_count == 0 {
+        1.0
+    } else {
+        total / window_count as f64
+    }
+}
+
+impl CteImage {
+    /// encode the image, then decode it back to compare it with this image
+    pub fn encode_with_metrics<W: Write>(
+        &self,
+        out: &mut W,
+        options: &EncodeOptions,
+    ) -> Result<QualityMetrics, CteEncodeError> {
+        let mut encoded = Vec::new();
+        self.encode_cte_with(&mut encoded, options)?;
+        out.write_all(&encoded)?;
+        // the format chosen with `auto_format` is always a built-in one
+        let codecs = match (
+            options.auto_format,
+            options.format.unwrap_or(self.original_format),
+        ) {
+            (None, CteFormat::Custom(codec)) => vec![codec],
+            _ => Vec::new(),
+        };
+        let decoded = CteImage::decode_cte_with(
+            &mut encoded.as_slice(),
+            &DecodeOptions {
+                codecs,
+                logical_size: Some(self.image.dimensions()),
+                mipmaps: false,
+                premultiplied_alpha: options.premultiplied_alpha,
+                ..DecodeOptions::default()
+            },
+        )
+        .map_err(CteEncodeError::EncodedImageInvalid)?;
+        Ok(QualityMetrics::compare(&self.image, &decoded.image))
+    }
+}
+
+/// the PSNR used by [`CteFormat::choose_for`]
+pub const DEFAULT_MIN_PSNR: f64 = 40.0;
+
+impl CteFormat {
+    /// the smallest built-in format the image can be stored in while keeping a PSNR of at least
+    /// [`DEFAULT_MIN_PSNR`]
+    pub fn choose_for(image: &DynamicImage) -> CteFormat {
+        Self::choose_for_with(image, DEFAULT_MIN_PSNR)
+    }
+
+    /// the smallest built-in format the image can be stored in while keeping a PSNR of at least
+    /// `min_psnr`, falling back to RGBA8888
+    pub fn choose_for_with(image: &DynamicImage, min_psnr: f64) -> CteFormat {
+        let pixels = image.to_rgba8();
+        let uses_alpha = pixels.pixels().any(|p| p.0[3] != 255);
+        let is_gray = pixels
+            .pixels()
+            .all(|p| p.0[0] == p.0[1] && p.0[1] == p.0[2]);
+        let is_white = pixels.pixels().all(|p| p.0[0..3] == [255, 255, 255]);
+
+        let mut candidates: Vec<CteFormat> = CteFormat::BUILTIN
+            .iter()
+            .copied()
+            .filter(|format| !uses_alpha || format.has_alpha())
+            .filter(|format| match format {
+                CteFormat::A8 | CteFormat::A4 => is_white,
+                CteFormat::L8 | CteFormat::L4 | CteFormat::La8 | CteFormat::La4 => is_gray,
+                // meant for normal maps, and drop the blue channel
+                CteFormat::Hilo8 => false,
+                _ => true,
+            })
+            .collect();
+        candidates.sort_by_key(|format| format.get_pixel_length_bit());
+
+        let source = CteImage::new(CteFormat::Rgba8888, image.clone());
+        for format in candidates {
+            let converted = match source.convert_to(format) {
+                Ok((converted, _)) => converted,
+                Err(_) => continue,
+            };
+            if QualityMetrics::compare(image, &converted.image).psnr >= min_psnr {
+                return format;
+            };
+        }
+        CteFormat::Rgba8888
+    }
+}
@@ -0,0 +1,173 @@
+use crate::gamma::{linear_to_srgb, srgb_to_linear};
+use crate::{CteFormat, CteFormatCodec, Etc1Quality};
+use alloc::vec::Vec;
+
+/// The method used to convert a colored pixel to a luminance value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LumaConversion {
+    /// the average of the red, green and blue channel
+    #[default]
+    Average,
+    /// the weighted sum defined by ITU-R BT.601 (0.299 R + 0.587 G + 0.114 B)
+    Rec601,
+    /// the weighted sum defined by ITU-R BT.709 (0.2126 R + 0.7152 G + 0.0722 B)
+    Rec709,
+    /// the weighted sum with these weights for the red, green and blue channel, divided by the
+    /// sum of the weights
+    Custom([u32; 3]),
+}
+
+impl LumaConversion {
+    pub fn convert(&self, pixel: [u8; 4]) -> u8 {
+        self.weight(pixel[0] as u64, pixel[1] as u64, pixel[2] as u64) as u8
+    }
+
+    /// the luminance of the given channels, which can be of any range
+    fn weight(&self, r: u64, g: u64, b: u64) -> u64 {
+        match self {
+            Self::Average => (r + g + b) / 3,
+            Self::Rec601 => (r * 299 + g * 587 + b * 114 + 500) / 1000,
+            Self::Rec709 => (r * 2126 + g * 7152 + b * 722 + 5000) / 10000,
+            Self::Custom(weights) => {
+                let total: u64 = weights.iter().map(|w| *w as u64).sum();
+                if total == 0 {
+                    return 0;
+                };
+                let sum = r * weights[0] as u64 + g * weights[1] as u64 + b * weights[2] as u64;
+                (sum + total / 2) / total
+            }
+        }
+    }
+}
+
+/// The mipmap levels to write after the main image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MipmapGeneration {
+    /// write the mipmaps stored in [`crate::CteImage::mipmaps`]
+    #[default]
+    Existing,
+    /// generate up to this number of mipmap levels by downscaling the main image
+    Generate(u32),
+}
+
+/// The dithering applied to the channels that are stored with only 4 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dithering {
+    /// round each pixel independently
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion
+    FloydSteinberg,
+    /// ordered dithering with a Bayer matrix of this size. The result of each pixel only depend
+    /// on its value and position. The size is rounded to a power of two between 2 and 16.
+    Ordered(u32),
+}
+
+/// Settings that affect how an image is encoded
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    /// the format to encode to. Use the format of the image if `None`.
+    pub format: Option<CteFormat>,
+    /// if set, ignore `format`, and use the smallest format that keep a PSNR of at least this
+    /// value (see [`crate::CteFormat::choose_for_with`]). Only used when encoding a `CteImage`.
+    pub auto_format: Option<f64>,
+    /// the offset the pixel data start at. Should be at least 28 (the size of the header).
+    pub pixel_start_offset: u32,
+    /// the byte used to fill the space between the header and the pixel data
+    pub padding_fill: u8,
+    /// the value of the unknown field of the header. If `None`, use the value from the header of
+    /// the decoded image, or 0 if there isn't one.
+    pub unk: Option<u32>,
+    /// fail instead of warning when some information of the image can't be stored
+    pub strict: bool,
+    /// pad the image with transparent pixels to a multiple of 8, instead of failing
+    pub pad: bool,
+    /// how colored pixel are converted to luminance, for the formats that only store luminance
+    pub luma: LumaConversion,
+    /// compute the luminance from linear light values instead of the sRGB encoded ones, converting
+    /// the result back to sRGB
+    pub linear_luma: bool,
+    /// the quality of the ETC1 compression
+    pub etc1_quality: Etc1Quality,
+    /// the mipmap levels written after the image
+    pub mipmaps: MipmapGeneration,
+    /// the dithering of the luminance and alpha of the 4 bit formats
+    pub dithering: Dithering,
+    /// if set, pixels with an alpha at least this value are made fully opaque, and the other
+    /// fully transparent
+    pub alpha_threshold: Option<u8>,
+    /// store the image with premultiplied alpha, multiplying the color channels by the alpha
+    pub premultiplied_alpha: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            auto_format: None,
+            pixel_start_offset: 128,
+            padding_fill: 0,
+            unk: None,
+            strict: false,
+            pad: false,
+            luma: LumaConversion::default(),
+            linear_luma: false,
+            etc1_quality: Etc1Quality::default(),
+            mipmaps: MipmapGeneration::default(),
+            dithering: Dithering::default(),
+            alpha_threshold: None,
+            premultiplied_alpha: false,
+        }
+    }
+}
+
+impl EncodeOptions {
+    /// the luminance of a pixel, according to `luma` and `linear_luma`
+    pub fn luminance(&self, pixel: [u8; 4]) -> u8 {
+        if !self.linear_luma {
+            return self.luma.convert(pixel);
+        };
+        let linear = self.luma.weight(
+            srgb_to_linear(pixel[0]) as u64,
+            srgb_to_linear(pixel[1]) as u64,
+            srgb_to_linear(pixel[2]) as u64,
+        );
+        linear_to_srgb(linear.min(u16::MAX as u64) as u16)
+    }
+}
+
+/// Settings that affect how a file is decoded
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// fail on inconsistent header. If false, try to decode the image anyway, keeping track of the
+    /// problems in [`crate::CteImage::decode_warnings`].
+    pub strict: bool,
+    /// codecs used for the format with a matching id, prefered over the built-in ones
+    pub codecs: Vec<&'static dyn CteFormatCodec>,
+    /// the size of the image, cropped from the stored one (which can only be a multiple of 8)
+    pub logical_size: Option<(u32, u32)>,
+    /// read the data after the image until the end of the input, and decode the mipmap levels it
+    /// contain. Off by default, as the header doesn't tell whether there are mipmaps, so the input
+    /// should only contain the file.
+    pub mipmaps: bool,
+    /// the image is stored with premultiplied alpha, so divide the color channels by the alpha.
+    /// Only used when decoding whole images.
+    pub premultiplied_alpha: bool,
+    /// expand the channels stored with 4 bits by multiplying them by 17, so the maximum value is
+    /// 255. If false, multiply them by 16 instead, like older version of this library did.
+    /// Only used when decoding whole images.
+    pub expand_nibbles_17: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            strict: true,
+            codecs: Vec::new(),
+            logical_size: None,
+            mipmaps: false,
+            premultiplied_alpha: false,
+            expand_nibbles_17: true,
+        }
+    }
+}
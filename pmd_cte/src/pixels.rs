@@ -0,0 +1,141 @@
+use crate::{CteEncodeError, CteFormat, CteImage};
+use image::{DynamicImage, GrayAlphaImage, GrayImage, Luma, LumaA, Rgb, RgbImage, Rgba, RgbaImage};
+
+/// The pixels of a decoded image, using the channels natively stored by its format
+#[derive(Debug, Clone)]
+pub enum DecodedPixels {
+    /// only the alpha channel is stored (the color being white)
+    Alpha(GrayImage),
+    Luma(GrayImage),
+    LumaAlpha(GrayAlphaImage),
+    Rgb(RgbImage),
+    Rgba(RgbaImage),
+}
+
+impl DecodedPixels {
+    /// separate the channels stored by a format from an image it was decoded to
+    pub(crate) fn from_image(image: &DynamicImage, alpha_only: bool) -> Self {
+        if alpha_only {
+            let rgba = image.to_rgba8();
+            return Self::Alpha(GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+                Luma([rgba.get_pixel(x, y).0[3]])
+            }));
+        };
+        match image {
+            DynamicImage::ImageLuma8(image) => Self::Luma(image.clone()),
+            DynamicImage::ImageLumaA8(image) => Self::LumaAlpha(image.clone()),
+            DynamicImage::ImageRgb8(image) => Self::Rgb(image.clone()),
+            image => Self::Rgba(image.to_rgba8()),
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Alpha(image) | Self::Luma(image) => image.dimensions(),
+            Self::LumaAlpha(image) => image.dimensions(),
+            Self::Rgb(image) => image.dimensions(),
+            Self::Rgba(image) => image.dimensions(),
+        }
+    }
+
+    /// convert the pixels to rgba, without losing information
+    pub fn to_rgba8(&self) -> RgbaImage {
+        match self {
+            Self::Alpha(image) => RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+                Rgba([255, 255, 255, image.get_pixel(x, y).0[0]])
+            }),
+            Self::Luma(image) => DynamicImage::ImageLuma8(image.clone()).to_rgba8(),
+            Self::LumaAlpha(image) => DynamicImage::ImageLumaA8(image.clone()).to_rgba8(),
+            Self::Rgb(image) => DynamicImage::ImageRgb8(image.clone()).to_rgba8(),
+            Self::Rgba(image) => image.clone(),
+        }
+    }
+}
+
+fn check_length(buffer: &[u8], width: u32, height: u32) -> Result<(), CteEncodeError> {
+    let expected_length = width as usize * height as usize;
+    if buffer.len() != expected_length {
+        return Err(CteEncodeError::PixelBufferSizeInvalid(
+            buffer.len(),
+            expected_length,
+        ));
+    };
+    Ok(())
+}
+
+/// Constructors from raw channel buffers, each one containing a byte per pixel and per channel,
+/// in row-major order
+impl CteImage {
+    /// create a [`CteFormat::L8`] image from its luminance
+    pub fn from_gray(width: u32, height: u32, gray: &[u8]) -> Result<Self, CteEncodeError> {
+        check_length(gray, width, height)?;
+        let image =
+            GrayImage::from_fn(width, height, |x, y| Luma([gray[(y * width + x) as usize]]));
+        Ok(Self::new(CteFormat::L8, DynamicImage::ImageLuma8(image)))
+    }
+
+    /// create a [`CteFormat::A8`] image from its alpha, the color being white
+    pub fn from_alpha(width: u32, height: u32, alpha: &[u8]) -> Result<Self, CteEncodeError> {
+        check_length(alpha, width, height)?;
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([255, 255, 255, alpha[(y * width + x) as usize]])
+        });
+        Ok(Self::new(CteFormat::A8, DynamicImage::ImageRgba8(image)))
+    }
+
+    /// create a [`CteFormat::La8`] image from separate luminance and alpha buffers
+    pub fn from_gray_alpha(
+        width: u32,
+        height: u32,
+        gray: &[u8],
+        alpha: &[u8],
+    ) -> Result<Self, CteEncodeError> {
+        check_length(gray, width, height)?;
+        check_length(alpha, width, height)?;
+        let image = GrayAlphaImage::from_fn(width, height, |x, y| {
+            let index = (y * width + x) as usize;
+            LumaA([gray[index], alpha[index]])
+        });
+        Ok(Self::new(CteFormat::La8, DynamicImage::ImageLumaA8(image)))
+    }
+
+    /// create a [`CteFormat::Rgb888`] image from separate red, green and blue buffers
+    pub fn from_rgb(
+        width: u32,
+        height: u32,
+        red: &[u8],
+        green: &[u8],
+        blue: &[u8],
+    ) -> Result<Self, CteEncodeError> {
+        for channel in [red, green, blue].iter() {
+            check_length(channel, width, height)?;
+        }
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            let index = (y * width + x) as usize;
+            Rgb([red[index], green[index], blue[index]])
+        });
+        Ok(Self::new(CteFormat::Rgb888, DynamicImage::ImageRgb8(image)))
+    }
+
+    /// create a [`CteFormat::Rgba8888`] image from separate red, green, blue and alpha buffers
+    pub fn from_rgba(
+        width: u32,
+        height: u32,
+        red: &[u8],
+        green: &[u8],
+        blue: &[u8],
+        alpha: &[u8],
+    ) -> Result<Self, CteEncodeError> {
+        for channel in [red, green, blue, alpha].iter() {
+            check_length(channel, width, height)?;
+        }
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            let index = (y * width + x) as usize;
+            Rgba([red[index], green[index], blue[index], alpha[index]])
+        });
+        Ok(Self::new(
+            CteFormat::Rgba8888,
+            DynamicImage::ImageRgba8(image),
+        ))
+    }
+}
@@ -0,0 +1,65 @@
+use crate::CteDecodeError;
+use std::io::{self, Read};
+
+/// A reader that keep track of the number of byte read, to report the offset of errors
+pub(crate) struct PositionTracker<R> {
+    pub inner: R,
+    pub position: u64,
+}
+
+impl<R> PositionTracker<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+}
+
+impl<R: Read> PositionTracker<R> {
+    /// skip the next `count` bytes, without allocating a buffer of that size (the offset of the
+    /// pixel data can be anything in a crafted file). Use `Seek` when possible.
+    pub fn skip(&mut self, count: u64) -> Result<(), CteDecodeError> {
+        let skipped = io::copy(&mut self.by_ref().take(count), &mut io::sink()).at(self.position)?;
+        if skipped < count {
+            return Err(CteDecodeError::IOError(
+                io::ErrorKind::UnexpectedEof.into(),
+                self.position,
+            ));
+        };
+        Ok(())
+    }
+
+    /// read the next `count` bytes, with a buffer that grow as they are read, so a crafted size
+    /// can't make it allocate more memory than the size of the input
+    pub fn read_bytes(&mut self, count: u64) -> Result<Vec<u8>, CteDecodeError> {
+        let mut data = Vec::new();
+        self.by_ref()
+            .take(count)
+            .read_to_end(&mut data)
+            .at(self.position)?;
+        if (data.len() as u64) < count {
+            return Err(CteDecodeError::IOError(
+                io::ErrorKind::UnexpectedEof.into(),
+                self.position,
+            ));
+        };
+        Ok(data)
+    }
+}
+
+impl<R: Read> Read for PositionTracker<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+pub(crate) trait IoResultExt<T> {
+    /// convert the io error to a [`CteDecodeError`] that occured at the given offset
+    fn at(self, offset: u64) -> Result<T, CteDecodeError>;
+}
+
+impl<T> IoResultExt<T> for io::Result<T> {
+    fn at(self, offset: u64) -> Result<T, CteDecodeError> {
+        self.map_err(|err| CteDecodeError::IOError(err, offset))
+    }
+}
@@ -0,0 +1,130 @@
+//! Vectorized conversions of the 4 bit luminance and alpha pairs of the La4 format, using SSE2
+//! on x86_64 (where it is always available) and a scalar fallback elsewhere. The fallback is
+//! compiled on every target, so it is checked against the SSE2 version.
+
+use crate::codec::{expand_4_bit, quantize_4_bit};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) use self::{expand_la4_scalar as expand_la4, pack_la4_scalar as pack_la4};
+
+/// expand each byte, containing the luminance in the high nibble and the alpha in the low one,
+/// to a rgba pixel
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn expand_la4(data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+    assert!(data.len() >= 64);
+    // safety: SSE2 is part of the x86_64 baseline, and the loads and stores stay within `data`
+    // and `pixels`, which are at least 64 bytes and exactly 256 bytes long
+    unsafe {
+        let low_mask = _mm_set1_epi8(0x0F);
+        let output = pixels.as_mut_ptr() as *mut __m128i;
+        for chunk in 0..4 {
+            let packed = _mm_loadu_si128(data.as_ptr().add(chunk * 16) as *const __m128i);
+            let high = _mm_and_si128(_mm_srli_epi16(packed, 4), low_mask);
+            let low = _mm_and_si128(packed, low_mask);
+            // multiply by 17, as each nibble is at most 15
+            let white = _mm_or_si128(high, _mm_slli_epi16(high, 4));
+            let alpha = _mm_or_si128(low, _mm_slli_epi16(low, 4));
+            let white_white = [
+                _mm_unpacklo_epi8(white, white),
+                _mm_unpackhi_epi8(white, white),
+            ];
+            let white_alpha = [
+                _mm_unpacklo_epi8(white, alpha),
+                _mm_unpackhi_epi8(white, alpha),
+            ];
+            for half in 0..2 {
+                let first = _mm_unpacklo_epi16(white_white[half], white_alpha[half]);
+                let second = _mm_unpackhi_epi16(white_white[half], white_alpha[half]);
+                _mm_storeu_si128(output.add(chunk * 4 + half * 2), first);
+                _mm_storeu_si128(output.add(chunk * 4 + half * 2 + 1), second);
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+pub(crate) fn expand_la4_scalar(data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+    for (pixel, v) in pixels.iter_mut().zip(data.iter()) {
+        let white = expand_4_bit(v >> 4);
+        *pixel = [white, white, white, expand_4_bit(v & 0x0F)];
+    }
+}
+
+/// quantize each 8 bit value of `values` to 4 bit, rounding to the nearest one
+#[cfg(target_arch = "x86_64")]
+unsafe fn quantize_4_bit_x16(values: __m128i) -> __m128i {
+    let zero = _mm_setzero_si128();
+    let eight = _mm_set1_epi16(8);
+    // (v + 8) / 17, as (v + 8) * 3856 >> 16 for every value up to 263
+    let reciprocal = _mm_set1_epi16(3856);
+    let low = _mm_mulhi_epu16(
+        _mm_add_epi16(_mm_unpacklo_epi8(values, zero), eight),
+        reciprocal,
+    );
+    let high = _mm_mulhi_epu16(
+        _mm_add_epi16(_mm_unpackhi_epi8(values, zero), eight),
+        reciprocal,
+    );
+    _mm_packus_epi16(low, high)
+}
+
+/// quantize the luminance and alpha of each pixel to 4 bit, and pack them in a byte
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn pack_la4(luma: &[u8; 64], alpha: &[u8; 64], out: &mut [u8]) {
+    assert!(out.len() >= 64);
+    // safety: SSE2 is part of the x86_64 baseline, and the loads and stores stay within the
+    // 64 bytes of `luma`, `alpha` and `out`
+    unsafe {
+        for chunk in 0..4 {
+            let luma = quantize_4_bit_x16(_mm_loadu_si128(
+                luma.as_ptr().add(chunk * 16) as *const __m128i
+            ));
+            let alpha = quantize_4_bit_x16(_mm_loadu_si128(
+                alpha.as_ptr().add(chunk * 16) as *const __m128i
+            ));
+            let packed = _mm_or_si128(_mm_slli_epi16(luma, 4), alpha);
+            _mm_storeu_si128(out.as_mut_ptr().add(chunk * 16) as *mut __m128i, packed);
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+pub(crate) fn pack_la4_scalar(luma: &[u8; 64], alpha: &[u8; 64], out: &mut [u8]) {
+    for ((l, a), v) in luma.iter().zip(alpha.iter()).zip(out.iter_mut()) {
+        *v = (quantize_4_bit(*l) << 4) | quantize_4_bit(*a);
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_la4_match_scalar() {
+        for start in (0..256).step_by(64) {
+            let data: alloc::vec::Vec<u8> = (start..start + 64).map(|v| v as u8).collect();
+            let (mut simd, mut scalar) = ([[0; 4]; 64], [[0; 4]; 64]);
+            expand_la4(&data, &mut simd);
+            expand_la4_scalar(&data, &mut scalar);
+            assert_eq!(simd, scalar, "bytes {} to {}", start, start + 63);
+        }
+    }
+
+    #[test]
+    fn pack_la4_match_scalar() {
+        for start in (0..256).step_by(64) {
+            let mut luma = [0; 64];
+            let mut alpha = [0; 64];
+            for (index, (luma, alpha)) in luma.iter_mut().zip(alpha.iter_mut()).enumerate() {
+                *luma = (start + index) as u8;
+                *alpha = (255 - start - index) as u8;
+            }
+            let (mut simd, mut scalar) = ([0; 64], [0; 64]);
+            pack_la4(&luma, &alpha, &mut simd);
+            pack_la4_scalar(&luma, &alpha, &mut scalar);
+            assert_eq!(simd, scalar, "values {} to {}", start, start + 63);
+        }
+    }
+}
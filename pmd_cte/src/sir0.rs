@@ -0,0 +1,120 @@
+//! Support for the SIR0 container some cte files are wrapped in. Its header point to the wrapped
+//! content, and to a list of the offsets of the pointers in the file, so they can be relocated
+//! when it is loaded.
+
+use crate::{check_header, CteDecodeError, CteEncodeError, CteHeader, CteImage};
+use crate::{DecodeOptions, EncodeOptions};
+use std::io::{Cursor, Write};
+
+pub const SIR0_MAGIC: [u8; 4] = *b"SIR0";
+const SIR0_HEADER_SIZE: usize = 16;
+/// the byte used to align the content and the pointer list to 16 bytes
+const SIR0_PADDING: u8 = 0xAA;
+
+/// return true if the data start with the magic of a SIR0 container
+pub fn is_sir0(data: &[u8]) -> bool {
+    data.starts_with(&SIR0_MAGIC)
+}
+
+/// return the content wrapped in a SIR0 container, from the offset the header point to up to the
+/// list of pointers. It may end with padding.
+pub fn unwrap_sir0(data: &[u8]) -> Result<&[u8], CteDecodeError> {
+    if data.len() < SIR0_HEADER_SIZE {
+        return Err(CteDecodeError::UnexpectedEnd(data.len() as u64));
+    };
+    if !is_sir0(data) {
+        return Err(CteDecodeError::InvalideHeader(
+            [data[0], data[1], data[2], data[3]],
+            0,
+        ));
+    };
+    let field = |offset: usize| {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]) as usize
+    };
+    let content_start = field(4);
+    let pointers_start = field(8);
+    if content_start < SIR0_HEADER_SIZE || content_start > data.len() {
+        return Err(CteDecodeError::Sir0PointerInvalid(content_start as u32, 4));
+    };
+    // the pointer list normally follow the content, but a file without one is still readable
+    let content_end = if pointers_start >= content_start && pointers_start <= data.len() {
+        pointers_start
+    } else {
+        data.len()
+    };
+    Ok(&data[content_start..content_end])
+}
+
+/// wrap the content in a SIR0 container. The only pointers it contain are the two of its header.
+pub fn wrap_sir0(content: &[u8]) -> Vec<u8> {
+    let align = |result: &mut Vec<u8>| {
+        let aligned_len = result.len().div_ceil(16) * 16;
+        result.resize(aligned_len, SIR0_PADDING);
+    };
+    let mut result = Vec::with_capacity(content.len() + 48);
+    result.extend_from_slice(&SIR0_MAGIC);
+    result.extend_from_slice(&[0; 12]);
+    result.extend_from_slice(content);
+    align(&mut result);
+    let pointers_start = result.len() as u32;
+    result[4..8].copy_from_slice(&(SIR0_HEADER_SIZE as u32).to_le_bytes());
+    result[8..12].copy_from_slice(&pointers_start.to_le_bytes());
+    // the offsets of the pointers at 4 and 8, each relative to the previous one, and the end
+    // of the list
+    result.extend_from_slice(&[4, 4, 0]);
+    align(&mut result);
+    result
+}
+
+impl CteImage {
+    /// decode a cte file stored in memory, which may be wrapped in a SIR0 container. The offsets
+    /// of the errors are relative to the start of the cte file.
+    pub fn decode_cte_sir0(data: &[u8]) -> Result<CteImage, CteDecodeError> {
+        Self::decode_cte_sir0_with(data, &DecodeOptions::default())
+    }
+
+    pub fn decode_cte_sir0_with(
+        data: &[u8],
+        options: &DecodeOptions,
+    ) -> Result<CteImage, CteDecodeError> {
+        if !is_sir0(data) {
+            return Self::decode_cte_with(&mut Cursor::new(data), options);
+        };
+        let mut content = unwrap_sir0(data)?;
+        // the alignment padding is smaller than a tile, so isn't reported as trailing data
+        if let Ok(header) = CteHeader::from_bytes(content) {
+            if let Ok((format, _)) = check_header(&header, options) {
+                let pixels_len = content
+                    .len()
+                    .saturating_sub(header.pixel_start_offset as usize);
+                let padding = pixels_len % format.get_tile_size();
+                let (body, end) = content.split_at(content.len() - padding);
+                if padding < 16 && end.iter().all(|byte| *byte == SIR0_PADDING) {
+                    content = body;
+                };
+            };
+        };
+        Self::decode_cte_with(&mut Cursor::new(content), options)
+    }
+
+    /// encode the image wrapped in a SIR0 container
+    pub fn encode_cte_sir0<W: Write>(&self, out: &mut W) -> Result<(), CteEncodeError> {
+        self.encode_cte_sir0_with(out, &EncodeOptions::default())
+    }
+
+    pub fn encode_cte_sir0_with<W: Write>(
+        &self,
+        out: &mut W,
+        options: &EncodeOptions,
+    ) -> Result<(), CteEncodeError> {
+        let mut content = Vec::new();
+        self.encode_cte_with(&mut content, options)?;
+        out.write_all(&wrap_sir0(&content))?;
+        Ok(())
+    }
+}
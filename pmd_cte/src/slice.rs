@@ -0,0 +1,130 @@
+//! Decoding and encoding from and to memory, without `std::io` nor the `image` crate, for use in
+//! `no_std` environment.
+
+use crate::{
+    check_header, decode_tiles, encode_tiles, level_byte_size, CteDecodeError, CteEncodeError,
+    CteFormat, CteHeader, DecodeOptions, EncodeOptions, CTE_HEADER_SIZE,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// decode the main image of a cte file stored in `data`, returning its header and its rgba
+/// pixels, row by row. Mipmaps are ignored.
+pub fn decode_rgba8(
+    data: &[u8],
+    options: &DecodeOptions,
+) -> Result<(CteHeader, Vec<u8>), CteDecodeError> {
+    let header = CteHeader::from_bytes(data)?;
+    let (format, _) = check_header(&header, options)?;
+    let codec = format.codec();
+    let start = header.pixel_start_offset.max(CTE_HEADER_SIZE as u32) as usize;
+    let pixel_data = data.get(start..).unwrap_or(&[]);
+    let expected = level_byte_size(header.width, header.height, codec);
+    if (pixel_data.len() as u64) < expected {
+        return Err(CteDecodeError::PixelDataTooShort(
+            pixel_data.len() as u64,
+            expected,
+            data.len() as u64,
+        ));
+    };
+    let mut pixel_data = pixel_data[..expected as usize].chunks(codec.tile_size());
+    // the biggest buffer that can be allocated is isize::MAX bytes long
+    let pixels_len = (header.width as usize)
+        .checked_mul(header.height as usize)
+        .and_then(|len| len.checked_mul(4))
+        .filter(|len| *len <= isize::MAX as usize)
+        .ok_or(CteDecodeError::ImageTooBig(
+            header.width,
+            header.height,
+            start as u64,
+        ))?;
+    let mut pixels = vec![0; pixels_len];
+    decode_tiles(
+        header.width,
+        header.height,
+        format,
+        &mut pixels,
+        options,
+        |section| {
+            // the length of the data was checked earlier
+            if let Some(tile) = pixel_data.next() {
+                section.copy_from_slice(tile);
+            };
+            Ok(())
+        },
+    )?;
+    Ok((header, pixels))
+}
+
+/// encode rgba pixels, stored row by row, into a cte file. The image is padded with transparent
+/// pixels if its dimensions aren't a multiple of 8 and `options.pad` is set. `options.mipmaps` is
+/// ignored.
+pub fn encode_rgba8(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, CteEncodeError> {
+    let expected_length = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|len| len.checked_mul(4))
+        .ok_or(CteEncodeError::ImageTooBig(width, height))?;
+    if pixels.len() != expected_length {
+        return Err(CteEncodeError::PixelBufferSizeInvalid(
+            pixels.len(),
+            expected_length,
+        ));
+    };
+    if !options.pad && !width.is_multiple_of(8) {
+        return Err(CteEncodeError::WidthNotMultiple8(width));
+    };
+    if !options.pad && !height.is_multiple_of(8) {
+        return Err(CteEncodeError::HeightNotMultiple8(height));
+    };
+    if options.pixel_start_offset < CTE_HEADER_SIZE as u32 {
+        return Err(CteEncodeError::ImageStartTooSoon(
+            options.pixel_start_offset,
+        ));
+    };
+    let stored_dimensions = (width.div_ceil(8) * 8, height.div_ceil(8) * 8);
+    let header = CteHeader {
+        format_id: format.get_id(),
+        width: stored_dimensions.0,
+        height: stored_dimensions.1,
+        pixel_length: format.get_pixel_length_bit(),
+        unk: options.unk.unwrap_or(0),
+        pixel_start_offset: options.pixel_start_offset,
+    };
+    let mut result = Vec::with_capacity(
+        options.pixel_start_offset as usize
+            + level_byte_size(stored_dimensions.0, stored_dimensions.1, format.codec()) as usize,
+    );
+    result.extend_from_slice(&header.to_bytes());
+    result.resize(options.pixel_start_offset as usize, options.padding_fill);
+    let mut alpha_discarded = false;
+    encode_tiles(
+        |x, y| {
+            if x < width && y < height {
+                let index = (y as usize * width as usize + x as usize) * 4;
+                [
+                    pixels[index],
+                    pixels[index + 1],
+                    pixels[index + 2],
+                    pixels[index + 3],
+                ]
+            } else {
+                [0, 0, 0, 0]
+            }
+        },
+        stored_dimensions,
+        format,
+        options,
+        &mut alpha_discarded,
+        |tile| {
+            result.extend_from_slice(tile);
+            Ok(())
+        },
+    )?;
+    Ok(result)
+}
@@ -0,0 +1,53 @@
+//! Mapping between the order pixels are stored in a 8×8 tile (a Z-order curve, starting from the
+//! bottom-left corner) and the usual row by row order, starting from the top-left corner.
+
+/// return the index of the pixel at the given coordinate in a tile, where the y axis goes up
+/// (the first row being the bottom of the tile once displayed)
+pub fn morton_index(x: u32, y: u32) -> usize {
+    ((x & 1) | (y & 1) << 1 | (x & 2) << 1 | (y & 2) << 2 | (x & 4) << 2 | (y & 4) << 3) as usize
+}
+
+/// return the index in a stored tile of the pixel at the given coordinate, where the y axis goes
+/// down (the first row being the top of the tile)
+pub fn tile_index(x: u32, y: u32) -> usize {
+    morton_index(x, 7 - y)
+}
+
+/// return the coordinate (with the y axis going down) of the pixel at the given index in a stored
+/// tile. This is the inverse of [`tile_index`].
+pub fn tile_coordinate(index: usize) -> (u32, u32) {
+    let index = index as u32;
+    let x = (index & 1) | (index >> 1 & 2) | (index >> 2 & 4);
+    let y = (index >> 1 & 1) | (index >> 2 & 2) | (index >> 3 & 4);
+    (x, 7 - y)
+}
+
+/// call `func` with the coordinate (with the y axis going down) and the value of each pixel of a
+/// stored tile, in the order they are stored in
+pub fn read_in_image_order<B, F>(buffer: &[B; 64], mut func: F)
+where
+    B: Clone,
+    F: FnMut(u32, u32, B),
+{
+    for (index, value) in buffer.iter().enumerate() {
+        let (x, y) = tile_coordinate(index);
+        func(x, y, value.clone());
+    }
+}
+
+/// convert the pixels of a stored tile into pixels stored row by row
+pub fn tile_to_linear<T: Copy>(tile: &[T; 64]) -> [T; 64] {
+    let mut linear = *tile;
+    read_in_image_order(tile, |x, y, v| linear[(y * 8 + x) as usize] = v);
+    linear
+}
+
+/// convert pixels stored row by row into the order they are stored in a tile
+pub fn linear_to_tile<T: Copy>(linear: &[T; 64]) -> [T; 64] {
+    let mut tile = *linear;
+    for (index, value) in tile.iter_mut().enumerate() {
+        let (x, y) = tile_coordinate(index);
+        *value = linear[(y * 8 + x) as usize];
+    }
+    tile
+}
@@ -0,0 +1,156 @@
+use crate::reader::{IoResultExt, PositionTracker};
+use crate::swizzle::tile_to_linear;
+use crate::CTE_HEADER_SIZE;
+use crate::{CteDecodeError, CteEncodeError, CteFormat, CteHeader, CteImage, DecodeOptions};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+/// A decoded 8×8 tile of an image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CteTile {
+    /// the x coordinate of the top-left pixel of this tile in the image
+    pub x: u32,
+    /// the y coordinate of the top-left pixel of this tile in the image
+    pub y: u32,
+    /// the rgba pixels of this tile, row by row
+    pub pixels: [[u8; 4]; 64],
+}
+
+impl CteTile {
+    /// the pixel at the given coordinate, relative to the tile
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        self.pixels[(y * 8 + x) as usize]
+    }
+}
+
+/// A tile of an image, as stored in the file (with the pixels in the swizzled order)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CteRawTile {
+    /// the x coordinate of the top-left pixel of this tile in the image
+    pub x: u32,
+    /// the y coordinate of the top-left pixel of this tile in the image
+    pub y: u32,
+    /// the encoded data of this tile, of the tile size of the format
+    pub data: Vec<u8>,
+}
+
+/// Read the tiles of the main image of a cte file one by one, in the order they are stored in
+/// (starting with the bottom row of tile).
+pub struct CteTileReader<R> {
+    input: PositionTracker<R>,
+    header: CteHeader,
+    format: CteFormat,
+    next_tile: u64,
+}
+
+impl<R: Read> CteTileReader<R> {
+    pub fn new(input: R) -> Result<Self, CteDecodeError> {
+        Self::with_options(input, &DecodeOptions::default())
+    }
+
+    pub fn with_options(input: R, options: &DecodeOptions) -> Result<Self, CteDecodeError> {
+        let mut input = PositionTracker::new(input);
+        let (header, format, _) = CteImage::read_checked_header(&mut input, options)?;
+        let distance_before_start = header
+            .pixel_start_offset
+            .saturating_sub(CTE_HEADER_SIZE as u32);
+        input.skip(distance_before_start as u64)?;
+        Ok(Self {
+            input,
+            header,
+            format,
+            next_tile: 0,
+        })
+    }
+
+    pub fn header(&self) -> &CteHeader {
+        &self.header
+    }
+
+    pub fn format(&self) -> CteFormat {
+        self.format
+    }
+
+    /// the total number of tile in the image
+    pub fn tile_count(&self) -> u64 {
+        (self.header.width / 8) as u64 * (self.header.height / 8) as u64
+    }
+
+    /// read the next tile without decoding it
+    pub fn next_raw(&mut self) -> Option<Result<CteRawTile, CteDecodeError>> {
+        if self.next_tile >= self.tile_count() {
+            return None;
+        };
+        let width_section = (self.header.width / 8) as u64;
+        let height_section = (self.header.height / 8) as u64;
+        // both are smaller than the width or height of the image
+        let x = (self.next_tile % width_section) as u32 * 8;
+        let y = (height_section - 1 - self.next_tile / width_section) as u32 * 8;
+        let mut data = vec![0; self.format.get_tile_size()];
+        if let Err(err) = self.input.read_exact(&mut data).at(self.input.position) {
+            // don't try to read the following tiles
+            self.next_tile = self.tile_count();
+            return Some(Err(err));
+        };
+        self.next_tile += 1;
+        Some(Ok(CteRawTile { x, y, data }))
+    }
+}
+
+impl<R: Read> Iterator for CteTileReader<R> {
+    type Item = Result<CteTile, CteDecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let raw = match self.next_raw()? {
+            Ok(raw) => raw,
+            Err(err) => return Some(Err(err)),
+        };
+        let mut tile_pixels = [[0; 4]; 64];
+        self.format.codec().decode_tile(&raw.data, &mut tile_pixels);
+        Some(Ok(CteTile {
+            x: raw.x,
+            y: raw.y,
+            pixels: tile_to_linear(&tile_pixels),
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.tile_count() - self.next_tile) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
+
+/// Write a cte file from already encoded tiles, in the order they are stored in (starting with
+/// the bottom row of tile).
+pub struct CteTileWriter<W> {
+    out: W,
+    tile_size: usize,
+}
+
+impl<W: Write> CteTileWriter<W> {
+    /// write the header, followed by zeroes up to the start of the pixel data
+    pub fn new(mut out: W, header: &CteHeader, format: CteFormat) -> Result<Self, CteEncodeError> {
+        if header.pixel_start_offset < CTE_HEADER_SIZE as u32 {
+            return Err(CteEncodeError::ImageStartTooSoon(header.pixel_start_offset));
+        };
+        header.write_with_padding(&mut out, 0)?;
+        Ok(Self {
+            out,
+            tile_size: format.get_tile_size(),
+        })
+    }
+
+    pub fn write_raw_tile(&mut self, data: &[u8]) -> Result<(), CteEncodeError> {
+        if data.len() != self.tile_size {
+            return Err(CteEncodeError::TileSizeInvalid(data.len(), self.tile_size));
+        };
+        self.out.write_all(data)?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    level_byte_size, mipmap_dimensions, CteDecodeError, CteDecodeWarning, CteFormat, CteHeader,
+    CteImage, CTE_HEADER_SIZE, FORMAT_ID_OFFSET, HEIGHT_OFFSET, PIXEL_LENGTH_OFFSET,
+    PIXEL_START_OFFSET_OFFSET, WIDTH_OFFSET,
+};
+use std::io::Read;
+
+/// the biggest texture dimension supported by the 3DS GPU
+const MAX_DIMENSION: u32 = 1024;
+
+/// Something found while checking a file
+#[derive(Debug)]
+pub enum ValidationFinding {
+    /// a problem that prevent the image from being decoded
+    Error(CteDecodeError),
+    /// something unusual, that doesn't prevent the image from being decoded
+    Warning(CteDecodeWarning),
+}
+
+/// The result of [`CteImage::validate`]
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// the header of the file, if it could be read
+    pub header: Option<CteHeader>,
+    pub findings: Vec<ValidationFinding>,
+}
+
+impl ValidationReport {
+    /// true if none of the findings prevent the image from being decoded
+    pub fn is_valid(&self) -> bool {
+        !self
+            .findings
+            .iter()
+            .any(|finding| matches!(finding, ValidationFinding::Error(_)))
+    }
+
+    fn error(&mut self, error: CteDecodeError) {
+        self.findings.push(ValidationFinding::Error(error));
+    }
+
+    fn warning(&mut self, warning: CteDecodeWarning) {
+        self.findings.push(ValidationFinding::Warning(warning));
+    }
+}
+
+impl CteImage {
+    /// check the whole file, reporting every problem found instead of stopping at the first one
+    pub fn validate<R: Read>(input: &mut R) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut data = Vec::new();
+        if let Err(err) = input.read_to_end(&mut data) {
+            report.error(CteDecodeError::IOError(err, data.len() as u64));
+            return report;
+        };
+        let header = match CteHeader::read(&mut &data[..]) {
+            Ok(header) => header,
+            Err(err) => {
+                report.error(err);
+                return report;
+            }
+        };
+        report.header = Some(header.clone());
+
+        let format = CteFormat::from_id(header.format_id);
+        match format {
+            Some(format) if !format.check_pixel_lenght_bit(header.pixel_length) => {
+                report.error(CteDecodeError::PixelLenghtInvalid(
+                    header.pixel_length,
+                    format,
+                    PIXEL_LENGTH_OFFSET,
+                ))
+            }
+            Some(_) => (),
+            None => report.error(CteDecodeError::UnsuportedFormat(
+                header.format_id,
+                FORMAT_ID_OFFSET,
+            )),
+        };
+        if header.pixel_start_offset < CTE_HEADER_SIZE as u32 {
+            report.error(CteDecodeError::ImageStartTooSoon(
+                header.pixel_start_offset,
+                PIXEL_START_OFFSET_OFFSET,
+            ));
+        };
+        let mut dimensions_valid = true;
+        if header.width % 8 != 0 {
+            report.error(CteDecodeError::WidthNotMultiple8(
+                header.width,
+                WIDTH_OFFSET,
+            ));
+            dimensions_valid = false;
+        };
+        if header.height % 8 != 0 {
+            report.error(CteDecodeError::HeightNotMultiple8(
+                header.height,
+                HEIGHT_OFFSET,
+            ));
+            dimensions_valid = false;
+        };
+        for dimension in &[header.width, header.height] {
+            if *dimension == 0 || *dimension > MAX_DIMENSION {
+                report.warning(CteDecodeWarning::DimensionOutOfRange(*dimension));
+            };
+        }
+
+        let format = match format {
+            Some(format) if dimensions_valid => format,
+            _ => return report,
+        };
+        let codec = format.codec();
+        let start = header.pixel_start_offset.max(CTE_HEADER_SIZE as u32) as u64;
+        let available = (data.len() as u64).saturating_sub(start);
+        let expected = level_byte_size(header.width, header.height, codec);
+        if available < expected {
+            report.error(CteDecodeError::PixelDataTooShort(
+                available,
+                expected,
+                data.len() as u64,
+            ));
+            return report;
+        };
+        let mut remaining = available - expected;
+        for (width, height) in mipmap_dimensions(header.width, header.height) {
+            let level_size = level_byte_size(width, height, codec);
+            if remaining < level_size {
+                break;
+            };
+            remaining -= level_size;
+        }
+        if remaining > 0 {
+            report.warning(CteDecodeWarning::TrailingData(remaining));
+        };
+        report
+    }
+}
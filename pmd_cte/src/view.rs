@@ -0,0 +1,84 @@
+use crate::swizzle::tile_to_linear;
+use crate::{
+    check_header, level_byte_size, CteDecodeError, CteFormat, CteHeader, DecodeOptions,
+    CTE_HEADER_SIZE,
+};
+
+/// A cte file stored in memory, whose tiles are only decoded when requested
+#[derive(Debug, Clone)]
+pub struct CteView<'a> {
+    header: CteHeader,
+    format: CteFormat,
+    /// the stored data of the main image
+    pixel_data: &'a [u8],
+}
+
+impl<'a> CteView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, CteDecodeError> {
+        Self::with_options(data, &DecodeOptions::default())
+    }
+
+    pub fn with_options(data: &'a [u8], options: &DecodeOptions) -> Result<Self, CteDecodeError> {
+        let header = CteHeader::from_bytes(data)?;
+        let (format, _) = check_header(&header, options)?;
+        let start = header.pixel_start_offset.max(CTE_HEADER_SIZE as u32) as usize;
+        let pixel_data = data.get(start..).unwrap_or(&[]);
+        let expected = level_byte_size(header.width, header.height, format.codec());
+        if (pixel_data.len() as u64) < expected {
+            return Err(CteDecodeError::PixelDataTooShort(
+                pixel_data.len() as u64,
+                expected,
+                data.len() as u64,
+            ));
+        };
+        Ok(Self {
+            header,
+            format,
+            pixel_data: &pixel_data[..expected as usize],
+        })
+    }
+
+    pub fn header(&self) -> &CteHeader {
+        &self.header
+    }
+
+    pub fn format(&self) -> CteFormat {
+        self.format
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.header.width, self.header.height)
+    }
+
+    /// the stored data of the main image, with every tile
+    pub fn pixel_data(&self) -> &'a [u8] {
+        self.pixel_data
+    }
+
+    /// the stored data of the tile containing the pixel at the given coordinate
+    pub fn raw_tile(&self, x: u32, y: u32) -> Option<&'a [u8]> {
+        if x >= self.header.width || y >= self.header.height {
+            return None;
+        };
+        let tile_size = self.format.get_tile_size();
+        let width_section = (self.header.width / 8) as usize;
+        let height_section = (self.header.height / 8) as usize;
+        let tile_index = (height_section - 1 - (y / 8) as usize) * width_section + (x / 8) as usize;
+        Some(&self.pixel_data[tile_index * tile_size..(tile_index + 1) * tile_size])
+    }
+
+    /// decode the tile containing the pixel at the given coordinate, returning its rgba pixels,
+    /// row by row
+    pub fn tile(&self, x: u32, y: u32) -> Option<[[u8; 4]; 64]> {
+        let raw = self.raw_tile(x, y)?;
+        let mut pixels = [[0; 4]; 64];
+        self.format.codec().decode_tile(raw, &mut pixels);
+        Some(tile_to_linear(&pixels))
+    }
+
+    /// decode the pixel at the given coordinate (decoding the whole tile containing it)
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let tile = self.tile(x, y)?;
+        Some(tile[((y % 8) * 8 + x % 8) as usize])
+    }
+}
@@ -0,0 +1,91 @@
+use image::{DynamicImage, RgbaImage};
+use pmd_cte::{clim_to_cte, ClimContainer, CteDecodeError, CteFormat, CteImage, EncodeOptions};
+use std::io::Cursor;
+
+/// a 20×12 image, whose dimensions are neither powers of two nor multiples of 8
+fn image(format: CteFormat) -> CteImage {
+    let image = RgbaImage::from_fn(20, 12, |x, y| {
+        image::Rgba([
+            (x * 12) as u8,
+            (y * 20) as u8,
+            (x * y) as u8,
+            (x + y * 20) as u8,
+        ])
+    });
+    CteImage::new(format, DynamicImage::ImageRgba8(image))
+}
+
+fn encode(image: &CteImage, container: ClimContainer) -> Vec<u8> {
+    image
+        .to_clim_with(container, &EncodeOptions::default())
+        .unwrap()
+}
+
+#[test]
+fn export_import_round_trip() {
+    for (container, magic) in [
+        (ClimContainer::Bclim, b"CLIM"),
+        (ClimContainer::Bflim, b"FLIM"),
+    ] {
+        let source = image(CteFormat::Rgba8888);
+        let data = encode(&source, container);
+        assert_eq!(&data[data.len() - 0x28..data.len() - 0x24], magic);
+
+        let decoded = CteImage::decode_clim(&data).unwrap();
+        assert_eq!(decoded.original_format, CteFormat::Rgba8888);
+        assert_eq!(decoded.image.to_rgba8(), source.image.to_rgba8());
+
+        // the cte file is padded to multiples of 8, with transparent pixels
+        let cte = clim_to_cte(&data, &EncodeOptions::default()).unwrap();
+        let converted = CteImage::decode_cte(&mut Cursor::new(&cte)).unwrap().image;
+        assert_eq!(converted.to_rgba8().dimensions(), (24, 16));
+        assert_eq!(
+            converted.crop_imm(0, 0, 20, 12).to_rgba8(),
+            source.image.to_rgba8()
+        );
+    }
+}
+
+#[test]
+fn clim_to_cte_keep_the_tiles() {
+    for format in [CteFormat::A4, CteFormat::Etc1A4] {
+        let source = image(format);
+        let padded = CteImage::new(
+            format,
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(24, 16, |x, y| {
+                if x < 20 && y < 12 {
+                    *source.image.to_rgba8().get_pixel(x, y)
+                } else {
+                    image::Rgba([0; 4])
+                }
+            })),
+        );
+        let options = EncodeOptions::default();
+        let cte = clim_to_cte(&encode(&source, ClimContainer::Bflim), &options).unwrap();
+        let mut expected = Vec::new();
+        padded.encode_cte_with(&mut expected, &options).unwrap();
+        assert_eq!(cte, expected, "{:?}", format);
+    }
+}
+
+#[test]
+fn truncated_footer() {
+    let data = encode(&image(CteFormat::La8), ClimContainer::Bclim);
+    // shorter than the footer
+    assert!(matches!(
+        CteImage::decode_clim(&data[data.len() - 0x20..]),
+        Err(CteDecodeError::UnexpectedEnd(0x20))
+    ));
+    // the end of the `imag` block is missing
+    assert!(matches!(
+        CteImage::decode_clim(&data[..data.len() - 4]),
+        Err(CteDecodeError::InvalideHeader(_, _))
+    ));
+    // the footer is complete, but not the pixel data before it
+    let mut missing_tiles = data[..0x100].to_vec();
+    missing_tiles.extend_from_slice(&data[data.len() - 0x28..]);
+    assert!(matches!(
+        CteImage::decode_clim(&missing_tiles),
+        Err(CteDecodeError::PixelDataTooShort(0x100, _, _))
+    ));
+}
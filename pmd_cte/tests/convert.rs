@@ -0,0 +1,36 @@
+use pmd_cte::{encode_rgba8, CteFormat, CteImage, EncodeOptions};
+use std::io::Cursor;
+
+/// a 16×16 LA4 image, using every combination of luminance and alpha
+fn la4_image() -> CteImage {
+    let pixels: Vec<u8> = (0..=255u8)
+        .flat_map(|index| {
+            let luminance = (index >> 4) * 17;
+            [luminance, luminance, luminance, (index & 0xF) * 17]
+        })
+        .collect();
+    let encoded = encode_rgba8(&pixels, 16, 16, CteFormat::La4, &EncodeOptions::default()).unwrap();
+    CteImage::decode_cte(&mut Cursor::new(&encoded)).unwrap()
+}
+
+#[test]
+fn la4_to_rgba4444() {
+    let source = la4_image();
+    let (converted, lossy) = source.convert_to(CteFormat::Rgba4444).unwrap();
+    assert!(!lossy);
+    assert_eq!(converted.original_format, CteFormat::Rgba4444);
+    assert_eq!(converted.image.to_rgba8(), source.image.to_rgba8());
+
+    let mut encoded = Vec::new();
+    converted.encode_cte(&mut encoded).unwrap();
+    let decoded = CteImage::decode_cte(&mut Cursor::new(&encoded)).unwrap();
+    assert_eq!(decoded.original_format, CteFormat::Rgba4444);
+    assert_eq!(decoded.image.to_rgba8(), source.image.to_rgba8());
+}
+
+#[test]
+fn rgba4444_to_a4_is_lossy() {
+    let (converted, _) = la4_image().convert_to(CteFormat::Rgba4444).unwrap();
+    let (_, lossy) = converted.convert_to(CteFormat::A4).unwrap();
+    assert!(lossy);
+}
@@ -0,0 +1,199 @@
+use pmd_cte::{decode_rgba8, encode_rgba8, CteFormat, CteHeader, DecodeOptions};
+use pmd_cte::{EncodeOptions, Etc1Quality};
+
+/// a cte file of a single 8×8 tile, made of the given blocks (preceded by their alpha for ETC1A4)
+fn tile_file(format: CteFormat, blocks: &[u64]) -> Vec<u8> {
+    let header = CteHeader {
+        format_id: format.get_id(),
+        width: 8,
+        height: 8,
+        pixel_length: format.get_pixel_length_bit(),
+        unk: 0,
+        pixel_start_offset: 28,
+    };
+    let mut result = header.to_bytes().to_vec();
+    for block in blocks {
+        result.extend_from_slice(&block.to_le_bytes());
+    }
+    result
+}
+
+/// decode a tile made of four time the same ETC1 block, and return the pixels of the first one,
+/// indexed by `x * 4 + y` like in the block itself
+fn decode_block(block: u64) -> [[u8; 3]; 16] {
+    let (_, pixels) = decode_rgba8(
+        &tile_file(CteFormat::Etc1, &[block; 4]),
+        &DecodeOptions::default(),
+    )
+    .unwrap();
+    let mut result = [[0; 3]; 16];
+    for x in 0..4 {
+        for y in 0..4 {
+            // the first block is at the bottom left of the displayed tile
+            let start = ((7 - y) * 8 + x) * 4;
+            result[x * 4 + y].copy_from_slice(&pixels[start..start + 3]);
+        }
+    }
+    result
+}
+
+#[test]
+fn individual_mode() {
+    // base colors 0x884422 and 0xff2233, with the tables 0 and 7, no flip, and every pixel using
+    // the first positive modifier
+    let block = (0x8 << 60)
+        | (0xF << 56)
+        | (0x4 << 52)
+        | (0x2 << 48)
+        | (0x2 << 44)
+        | (0x3 << 40)
+        | (7 << 34);
+    let decoded = decode_block(block);
+    for x in 0..4 {
+        for y in 0..4 {
+            let expected = if x < 2 {
+                [0x8A, 0x46, 0x24]
+            } else {
+                // red is clamped to 255
+                [255, 0x22 + 47, 0x33 + 47]
+            };
+            assert_eq!(decoded[x * 4 + y], expected, "pixel {}, {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn differential_mode_with_flip() {
+    // base colors (16, 8, 0) and (16 - 4, 8 + 3, 0 + 0) on 5 bits, with the tables 1 and 4,
+    // flipped. The pixels of the row y use the modifier y: +a, +b, -a, -b.
+    let block = (16 << 59)
+        | (0b100 << 56)
+        | (8 << 51)
+        | (0b011 << 48)
+        | (1 << 37)
+        | (4 << 34)
+        | (1 << 33)
+        | (1 << 32)
+        | (0xCCCC << 16)
+        | 0xAAAA;
+    let decoded = decode_block(block);
+    // 16, 12, 8 and 11 expand to 132, 99, 66 and 90
+    let expected_rows = [
+        [132 + 5, 66 + 5, 5],
+        [132 + 17, 66 + 17, 17],
+        // blue is clamped to 0
+        [99 - 18, 90 - 18, 0],
+        [99 - 60, 90 - 60, 0],
+    ];
+    for x in 0..4 {
+        for (y, expected) in expected_rows.iter().enumerate() {
+            assert_eq!(decoded[x * 4 + y], *expected, "pixel {}, {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn flip_bit_select_the_halves() {
+    // the base colors 0xf00 and 0x000, split vertically then horizontally
+    let block = 0xF << 60;
+    let vertical = decode_block(block);
+    let horizontal = decode_block(block | (1 << 32));
+    for x in 0..4 {
+        for y in 0..4 {
+            let red = |first_half: bool| if first_half { 255 } else { 2 };
+            assert_eq!(vertical[x * 4 + y][0], red(x < 2), "pixel {}, {}", x, y);
+            assert_eq!(horizontal[x * 4 + y][0], red(y < 2), "pixel {}, {}", x, y);
+        }
+    }
+}
+
+/// encode then decode a 16×16 image, returning the biggest difference of a color channel and the
+/// sum of the squared differences
+fn round_trip_error(pixels: &[u8], quality: Etc1Quality) -> (i32, u64) {
+    let options = EncodeOptions {
+        etc1_quality: quality,
+        ..EncodeOptions::default()
+    };
+    let encoded = encode_rgba8(pixels, 16, 16, CteFormat::Etc1, &options).unwrap();
+    let (_, decoded) = decode_rgba8(&encoded, &DecodeOptions::default()).unwrap();
+    let mut max = 0;
+    let mut total = 0;
+    for (source, decoded) in pixels.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+        for channel in 0..3 {
+            let difference = (source[channel] as i32 - decoded[channel] as i32).abs();
+            max = max.max(difference);
+            total += (difference * difference) as u64;
+        }
+    }
+    (max, total)
+}
+
+/// a 16×16 opaque image
+fn image(pixel: fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+    (0..16 * 16)
+        .flat_map(|index| {
+            let [r, g, b] = pixel(index % 16, index / 16);
+            [r, g, b, 255]
+        })
+        .collect()
+}
+
+#[test]
+fn encode_gradient() {
+    let gradient = image(|x, y| [(x * 16) as u8, (y * 16) as u8, 128]);
+    let (fast_max, fast_total) = round_trip_error(&gradient, Etc1Quality::Fast);
+    let (high_max, high_total) = round_trip_error(&gradient, Etc1Quality::High);
+    assert!(fast_max <= 24, "{}", fast_max);
+    assert!(high_max <= 24, "{}", high_max);
+    assert!(high_total <= fast_total, "{} > {}", high_total, fast_total);
+}
+
+#[test]
+fn encode_small_variations() {
+    let pixels = image(|x, y| [100 + (x % 4 * 3) as u8, 50 + (y % 4 * 5) as u8, 200]);
+    for quality in [Etc1Quality::Fast, Etc1Quality::High] {
+        let (max, _) = round_trip_error(&pixels, quality);
+        assert!(max <= 8, "{:?}: {}", quality, max);
+    }
+}
+
+#[test]
+fn encode_halves_of_different_colors() {
+    // only the individual mode can store two so different colors, and each image need a
+    // different flip bit
+    let rows = image(|_, y| if y % 4 < 2 { [255, 0, 0] } else { [0, 0, 255] });
+    let columns = image(|x, _| if x % 4 < 2 { [255, 0, 0] } else { [0, 0, 255] });
+    for quality in [Etc1Quality::Fast, Etc1Quality::High] {
+        for pixels in [&rows, &columns] {
+            let (max, _) = round_trip_error(pixels, quality);
+            assert!(max <= 2, "{:?}: {}", quality, max);
+        }
+    }
+}
+
+#[test]
+fn etc1a4_alpha_before_color() {
+    // the alpha of the pixel of index i is i, and the color is 0x888888 + 2
+    let alpha = 0xFEDC_BA98_7654_3210;
+    let color = (0x88 << 56) | (0x88 << 48) | (0x88 << 40);
+    let file = tile_file(CteFormat::Etc1A4, &[alpha, color].repeat(4));
+    let (_, pixels) = decode_rgba8(&file, &DecodeOptions::default()).unwrap();
+    for x in 0..4 {
+        for y in 0..4 {
+            let start = ((7 - y) * 8 + x) * 4;
+            let expected_alpha = (x * 4 + y) as u8 * 17;
+            assert_eq!(
+                pixels[start..start + 4],
+                [0x8A, 0x8A, 0x8A, expected_alpha],
+                "pixel {}, {}",
+                x,
+                y
+            );
+        }
+    }
+
+    let encoded =
+        encode_rgba8(&pixels, 8, 8, CteFormat::Etc1A4, &EncodeOptions::default()).unwrap();
+    let data = &encoded[EncodeOptions::default().pixel_start_offset as usize..];
+    assert_eq!(data[..8], alpha.to_le_bytes());
+}
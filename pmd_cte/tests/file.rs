@@ -0,0 +1,34 @@
+use image::{DynamicImage, GenericImageView, GrayAlphaImage, LumaA};
+use pmd_cte::CteFormat;
+use std::env;
+use std::fs;
+
+#[test]
+fn save_and_open_cte() {
+    let dir = env::temp_dir().join(format!("pmd_cte_file_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    // a font-like image, whose dimensions aren't multiples of 8
+    let image = DynamicImage::ImageLumaA8(GrayAlphaImage::from_fn(12, 10, |x, y| {
+        LumaA([255, (x * 20 + y) as u8])
+    }));
+
+    let path = dir.join("font.img");
+    pmd_cte::save(&image, &path).unwrap();
+    let data = fs::read(&path).unwrap();
+    assert!(data.starts_with(b"\0cte"));
+    assert_eq!(data[4..8], CteFormat::A8.get_id().to_le_bytes());
+
+    let opened = pmd_cte::open(&path).unwrap();
+    assert_eq!(opened.dimensions(), (16, 16));
+    assert_eq!(opened.crop_imm(0, 0, 12, 10).to_rgba8(), image.to_rgba8());
+    assert!(opened
+        .to_rgba8()
+        .enumerate_pixels()
+        .all(|(x, y, pixel)| x < 12 && y < 10 || pixel.0[3] == 0));
+
+    // the other formats are handled by the image crate
+    let path = dir.join("font.png");
+    pmd_cte::save(&image, &path).unwrap();
+    assert_eq!(pmd_cte::open(&path).unwrap().to_rgba8(), image.to_rgba8());
+    fs::remove_dir_all(&dir).unwrap();
+}
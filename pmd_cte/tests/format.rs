@@ -0,0 +1,12 @@
+use pmd_cte::CteFormat;
+
+#[test]
+fn every_builtin_name_is_parsed() {
+    for format in CteFormat::BUILTIN {
+        let name = format.name().unwrap();
+        assert_eq!(name.parse::<CteFormat>(), Ok(format));
+        assert_eq!(name.to_uppercase().parse::<CteFormat>(), Ok(format));
+    }
+    assert_eq!("rgba4444".parse::<CteFormat>(), Ok(CteFormat::Rgba4444));
+    assert!("rgba4443".parse::<CteFormat>().is_err());
+}
@@ -0,0 +1,83 @@
+//! Inputs found by the targets in `fuzz/` that used to make the crate panic
+
+use pmd_cte::{
+    decode_rgba8, encode_rgba8, CteDecodeError, CteEncodeError, CteFormat, CteHeader, CteImage,
+    CteTileReader, CteView,
+};
+use pmd_cte::{DecodeOptions, EncodeOptions};
+use std::io::Cursor;
+
+/// an empty rgba8888 image, whose pixel data start far after the end of the file
+const PIXEL_START_PAST_THE_END: [u8; 28] = [
+    0, 99, 116, 101, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 10,
+];
+
+/// a rgba8888 image of 4227858432×2449473568 pixels, without any pixel data
+const HUGE_DIMENSIONS: [u8; 35] = [
+    0, 99, 116, 101, 0, 0, 0, 0, 0, 0, 0, 252, 32, 0, 0, 146, 32, 0, 0, 0, 166, 0, 41, 0, 0, 0, 0,
+    32, 13, 58, 0, 128, 0, 198, 198,
+];
+
+#[test]
+fn pixel_start_past_the_end() {
+    assert!(matches!(
+        decode_rgba8(&PIXEL_START_PAST_THE_END, &DecodeOptions::default()),
+        Ok((_, pixels)) if pixels.is_empty()
+    ));
+    assert!(CteView::new(&PIXEL_START_PAST_THE_END).is_ok());
+    assert!(matches!(
+        CteImage::decode_cte(&mut Cursor::new(&PIXEL_START_PAST_THE_END)),
+        Err(CteDecodeError::IOError(_, _))
+    ));
+}
+
+#[test]
+fn huge_dimensions() {
+    assert!(matches!(
+        CteImage::decode_cte(&mut Cursor::new(&HUGE_DIMENSIONS)),
+        Err(CteDecodeError::IOError(_, _))
+    ));
+    assert!(matches!(
+        decode_rgba8(&HUGE_DIMENSIONS, &DecodeOptions::default()),
+        Err(CteDecodeError::PixelDataTooShort(_, _, _))
+    ));
+    assert!(matches!(
+        CteImage::decode_cte_into(
+            &mut Cursor::new(&HUGE_DIMENSIONS),
+            &mut [0; 256],
+            &DecodeOptions::default()
+        ),
+        Err(CteDecodeError::BufferTooSmall(_, _, _))
+    ));
+    let header = CteHeader {
+        pixel_start_offset: 28,
+        ..CteHeader::from_bytes(&HUGE_DIMENSIONS).unwrap()
+    };
+    let mut tiles = CteTileReader::new(Cursor::new(header.to_bytes())).unwrap();
+    assert_eq!(tiles.tile_count(), 528482304 * 306184196);
+    assert!(matches!(
+        tiles.next(),
+        Some(Err(CteDecodeError::IOError(_, _)))
+    ));
+    assert!(tiles.next().is_none());
+    assert!(matches!(
+        encode_rgba8(
+            &[],
+            u32::MAX,
+            u32::MAX,
+            CteFormat::A8,
+            &EncodeOptions::default()
+        ),
+        Err(CteEncodeError::ImageTooBig(_, _))
+    ));
+}
+
+#[test]
+fn gray_values_are_decoded_unchanged() {
+    let pixels: Vec<u8> = (0..=255).flat_map(|v| [v, v, v, 255]).collect();
+    for format in [CteFormat::L8, CteFormat::La8] {
+        let encoded = encode_rgba8(&pixels, 16, 16, format, &EncodeOptions::default()).unwrap();
+        let decoded = CteImage::decode_cte(&mut Cursor::new(&encoded)).unwrap();
+        assert_eq!(decoded.image.to_rgba8().into_raw(), pixels, "{:?}", format);
+    }
+}
@@ -0,0 +1,49 @@
+use image::GenericImageView;
+use pmd_cte::{encode_rgba8, CteFormat, CteImage, EncodeOptions, LazyCteImage};
+use std::io::Cursor;
+
+/// a 24×16 image where every pixel is different, encoded in the given format
+fn encoded_image(format: CteFormat) -> Vec<u8> {
+    let pixels: Vec<u8> = (0..24 * 16)
+        .flat_map(|index: u32| {
+            let (x, y) = (index % 24, index / 24);
+            [
+                (x * 10) as u8,
+                (y * 15) as u8,
+                (x * y) as u8,
+                (index * 7) as u8,
+            ]
+        })
+        .collect();
+    encode_rgba8(&pixels, 24, 16, format, &EncodeOptions::default()).unwrap()
+}
+
+#[test]
+fn regions_match_decode_cte() {
+    for format in [CteFormat::Rgba8888, CteFormat::La4, CteFormat::Etc1A4] {
+        let data = encoded_image(format);
+        let decoded = CteImage::decode_cte(&mut Cursor::new(&data))
+            .unwrap()
+            .image
+            .to_rgba8();
+        let mut lazy = LazyCteImage::new(Cursor::new(&data)).unwrap();
+        assert_eq!(lazy.dimensions(), (24, 16));
+        // the whole image, a region inside a tile, and regions across tiles, not aligned on them
+        for (x, y, width, height) in [(0, 0, 24, 16), (2, 3, 4, 4), (5, 3, 13, 11), (7, 1, 10, 2)] {
+            let region = lazy.decode_region(x, y, width, height).unwrap();
+            assert_eq!(
+                region,
+                decoded.view(x, y, width, height).to_image(),
+                "{:?}: region {}, {}, {}×{}",
+                format,
+                x,
+                y,
+                width,
+                height
+            );
+        }
+        // the part outside of the image is cropped out
+        let region = lazy.decode_region(20, 10, 10, 10).unwrap();
+        assert_eq!(region, decoded.view(20, 10, 4, 6).to_image());
+    }
+}
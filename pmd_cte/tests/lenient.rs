@@ -0,0 +1,56 @@
+use pmd_cte::{encode_rgba8, CteFormat, CteHeader, CteImage, DecodeOptions, EncodeOptions};
+use std::io::Cursor;
+
+/// a 8×8 rgba8888 file, whose header claim the pixel data start inside it
+fn pixels_inside_header() -> Vec<u8> {
+    let pixels: Vec<u8> = (0..64 * 4).map(|index| (index * 7) as u8).collect();
+    let mut file = encode_rgba8(
+        &pixels,
+        8,
+        8,
+        CteFormat::Rgba8888,
+        &EncodeOptions::default(),
+    )
+    .unwrap();
+    let start = EncodeOptions::default().pixel_start_offset as usize;
+    file.drain(28..start);
+    let mut header = CteHeader::from_bytes(&file[..28]).unwrap();
+    header.pixel_start_offset = 12;
+    file[..28].copy_from_slice(&header.to_bytes());
+    file
+}
+
+#[test]
+fn pixel_start_inside_header() {
+    let file = pixels_inside_header();
+    let options = DecodeOptions {
+        strict: false,
+        ..DecodeOptions::default()
+    };
+    let read = CteImage::decode_cte_with(&mut Cursor::new(&file), &options).unwrap();
+    let seeked = CteImage::decode_cte_seek_with(&mut Cursor::new(&file), &options).unwrap();
+    assert_eq!(read.image.to_rgba8(), seeked.image.to_rgba8());
+    assert_eq!(read.decode_warnings, seeked.decode_warnings);
+    assert!(!read.decode_warnings.is_empty());
+
+    let strict = DecodeOptions::default();
+    assert!(CteImage::decode_cte_seek_with(&mut Cursor::new(&file), &strict).is_err());
+}
+
+#[test]
+fn tile_offset_of_partial_tiles() {
+    let header = |height| CteHeader {
+        format_id: CteFormat::A8.get_id(),
+        width: 16,
+        height,
+        pixel_length: 8,
+        unk: 0,
+        pixel_start_offset: 128,
+    };
+    // the rows of tiles are stored from the bottom to the top
+    assert_eq!(header(4).tile_offset(64, 9, 3), Some(128 + 64));
+    assert_eq!(header(4).tile_offset(64, 0, 4), None);
+    assert_eq!(header(12).tile_offset(64, 0, 0), Some(128 + 2 * 64));
+    assert_eq!(header(12).tile_offset(64, 9, 11), Some(128 + 64));
+    assert_eq!(header(12).tile_offset(64, 0, 12), None);
+}
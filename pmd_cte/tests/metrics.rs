@@ -0,0 +1,48 @@
+use image::{ColorType, DynamicImage, GrayAlphaImage, LumaA};
+use pmd_cte::codec::{A8Codec, CteFormatCodec};
+use pmd_cte::{CteFormat, CteImage, EncodeOptions};
+
+/// the A8 format, with another id
+#[derive(Debug)]
+struct CustomA8;
+
+impl CteFormatCodec for CustomA8 {
+    fn id(&self) -> u32 {
+        0x100
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        8
+    }
+
+    fn has_alpha(&self) -> bool {
+        true
+    }
+
+    fn color_type(&self) -> ColorType {
+        ColorType::La8
+    }
+
+    fn decode_tile(&self, data: &[u8], pixels: &mut [[u8; 4]; 64]) {
+        A8Codec.decode_tile(data, pixels)
+    }
+
+    fn encode_tile(&self, pixels: &[[u8; 4]; 64], options: &EncodeOptions, out: &mut [u8]) {
+        A8Codec.encode_tile(pixels, options, out)
+    }
+}
+
+#[test]
+fn metrics_of_custom_format() {
+    let image = GrayAlphaImage::from_fn(16, 8, |x, y| LumaA([255, (x * 16 + y) as u8]));
+    let image = CteImage::new(
+        CteFormat::Custom(&CustomA8),
+        DynamicImage::ImageLumaA8(image),
+    );
+    let mut encoded = Vec::new();
+    let metrics = image
+        .encode_with_metrics(&mut encoded, &EncodeOptions::default())
+        .unwrap();
+    assert_eq!(metrics.psnr, f64::INFINITY);
+    assert_eq!(encoded[4..8], 0x100u32.to_le_bytes());
+}
@@ -0,0 +1,93 @@
+use pmd_cte::{decode_rgba8, encode_rgba8, CteFormat, DecodeOptions, EncodeOptions};
+
+/// the biggest difference allowed between a value and its decoded 4 bit approximation
+const NIBBLE_TOLERANCE: i32 = 8;
+
+/// a 16×16 image where each pixel has a different value, from 0 to 255
+fn gradient(pixel: fn(u8) -> [u8; 4]) -> Vec<u8> {
+    (0..=255).flat_map(pixel).collect()
+}
+
+fn round_trip(format: CteFormat, pixels: &[u8]) -> Vec<u8> {
+    let encoded = encode_rgba8(pixels, 16, 16, format, &EncodeOptions::default()).unwrap();
+    let (_, decoded) = decode_rgba8(&encoded, &DecodeOptions::default()).unwrap();
+    decoded
+}
+
+fn assert_channel_close(source: &[u8], decoded: &[u8], channel: usize) {
+    for (index, (source, decoded)) in source
+        .chunks_exact(4)
+        .zip(decoded.chunks_exact(4))
+        .enumerate()
+    {
+        let difference = (source[channel] as i32 - decoded[channel] as i32).abs();
+        assert!(
+            difference <= NIBBLE_TOLERANCE,
+            "pixel {}: {} decoded as {}",
+            index,
+            source[channel],
+            decoded[channel]
+        );
+    }
+}
+
+#[test]
+fn l4_round_trip() {
+    let source = gradient(|v| [v, v, v, 255]);
+    let decoded = round_trip(CteFormat::L4, &source);
+    assert_channel_close(&source, &decoded, 0);
+}
+
+#[test]
+fn a4_round_trip() {
+    let source = gradient(|v| [255, 255, 255, v]);
+    let decoded = round_trip(CteFormat::A4, &source);
+    assert_channel_close(&source, &decoded, 3);
+}
+
+#[test]
+fn la4_round_trip() {
+    let source = gradient(|v| [v, v, v, 255 - v]);
+    let decoded = round_trip(CteFormat::La4, &source);
+    assert_channel_close(&source, &decoded, 0);
+    assert_channel_close(&source, &decoded, 3);
+}
+
+#[test]
+fn la4_keep_luminance_and_alpha_separate() {
+    let source = gradient(|_| [255, 255, 255, 0]);
+    let decoded = round_trip(CteFormat::La4, &source);
+    assert!(decoded.chunks_exact(4).all(|p| p[0] >= 240 && p[3] == 0));
+}
+
+#[test]
+fn etc1a4_alpha_round_trip() {
+    let source = gradient(|v| [128, 128, 128, v]);
+    let decoded = round_trip(CteFormat::Etc1A4, &source);
+    assert_channel_close(&source, &decoded, 3);
+}
+
+#[test]
+fn rgba4444_round_trip() {
+    let source = gradient(|v| [v, 255 - v, v / 2, v.wrapping_mul(3)]);
+    let decoded = round_trip(CteFormat::Rgba4444, &source);
+    for channel in 0..4 {
+        assert_channel_close(&source, &decoded, channel);
+    }
+}
+
+#[test]
+fn sixteen_bit_layouts() {
+    for (format, pixel, stored) in [
+        (CteFormat::Rgba5551, [255, 0, 132, 255], 0xF821u16),
+        (CteFormat::Rgba4444, [0x11, 0x22, 0x33, 0x44], 0x1234),
+        (CteFormat::Hilo8, [0x12, 0x34, 0, 255], 0x1234),
+    ] {
+        let pixels = pixel.repeat(64);
+        let encoded = encode_rgba8(&pixels, 8, 8, format, &EncodeOptions::default()).unwrap();
+        let data = &encoded[EncodeOptions::default().pixel_start_offset as usize..];
+        assert_eq!(data[..2], stored.to_le_bytes(), "{:?}", format);
+        let (_, decoded) = decode_rgba8(&encoded, &DecodeOptions::default()).unwrap();
+        assert_eq!(decoded, pixels, "{:?}", format);
+    }
+}
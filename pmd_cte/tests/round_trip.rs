@@ -0,0 +1,100 @@
+use pmd_cte::{decode_rgba8, encode_rgba8, CteFormat, DecodeOptions, EncodeOptions};
+use proptest::prelude::*;
+use proptest::sample::select;
+
+/// a random rgba image, with dimensions that are multiples of 8
+fn image() -> impl Strategy<Value = (u32, u32, Vec<u8>)> {
+    (1..=4u32, 1..=4u32).prop_flat_map(|(width, height)| {
+        let pixels = (width * 8 * height * 8 * 4) as usize;
+        (
+            Just(width * 8),
+            Just(height * 8),
+            prop::collection::vec(any::<u8>(), pixels),
+        )
+    })
+}
+
+/// the biggest difference allowed between each channel of a pixel and its decoded value, or
+/// `None` if the format doesn't store it
+fn tolerance(format: CteFormat) -> [Option<i32>; 4] {
+    let color = |tolerance| [Some(tolerance); 3];
+    let (color, alpha) = match format {
+        CteFormat::Rgba8888 => (color(0), Some(0)),
+        CteFormat::Rgb888 => (color(0), None),
+        // the alpha is either 0 or 255
+        CteFormat::Rgba5551 => (color(4), Some(127)),
+        // 5 bits for red and blue, and 6 for green
+        CteFormat::Rgb565 => ([Some(4), Some(2), Some(4)], None),
+        CteFormat::Rgba4444 => (color(8), Some(8)),
+        CteFormat::La8 => (color(0), Some(0)),
+        // the blue channel isn't stored
+        CteFormat::Hilo8 => ([Some(0), Some(0), None], None),
+        CteFormat::L8 => (color(0), None),
+        CteFormat::A8 => ([None; 3], Some(0)),
+        CteFormat::La4 => (color(8), Some(8)),
+        CteFormat::L4 => (color(8), None),
+        CteFormat::A4 => ([None; 3], Some(8)),
+        // the images are made of 4×4 blocks of a single color, which ETC1 can't always represent
+        // exactly either
+        CteFormat::Etc1 => (color(8), None),
+        CteFormat::Etc1A4 => (color(8), Some(8)),
+        CteFormat::Custom(_) => unreachable!(),
+    };
+    [color[0], color[1], color[2], alpha]
+}
+
+/// change the image into one the format can store within its quantization error: the luminance
+/// formats get gray pixels, and the ETC1 ones get 4×4 blocks of the same color
+fn representable(format: CteFormat, width: u32, pixels: &mut [u8]) {
+    let width = width as usize;
+    let original = pixels.to_vec();
+    for (index, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        match format {
+            CteFormat::La8 | CteFormat::L8 | CteFormat::La4 | CteFormat::L4 => {
+                pixel[1] = pixel[0];
+                pixel[2] = pixel[0];
+            }
+            CteFormat::Etc1 | CteFormat::Etc1A4 => {
+                let (x, y) = (index % width, index / width);
+                let block_start = ((y - y % 4) * width + x - x % 4) * 4;
+                pixel[0..3].copy_from_slice(&original[block_start..block_start + 3]);
+            }
+            _ => (),
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn decode_encode_within_quantization_error(
+        format in select(CteFormat::BUILTIN.to_vec()),
+        (width, height, mut pixels) in image(),
+    ) {
+        representable(format, width, &mut pixels);
+        let encoded = encode_rgba8(&pixels, width, height, format, &EncodeOptions::default())
+            .unwrap();
+        let (header, decoded) = decode_rgba8(&encoded, &DecodeOptions::default()).unwrap();
+        prop_assert_eq!((header.width, header.height), (width, height));
+        prop_assert_eq!(header.format_id, format.get_id());
+        let tolerance = tolerance(format);
+        for (index, (source, decoded)) in pixels
+            .chunks_exact(4)
+            .zip(decoded.chunks_exact(4))
+            .enumerate()
+        {
+            for channel in 0..4 {
+                if let Some(tolerance) = tolerance[channel] {
+                    let difference = (source[channel] as i32 - decoded[channel] as i32).abs();
+                    prop_assert!(
+                        difference <= tolerance,
+                        "{:?}, pixel {}: {:?} decoded as {:?}",
+                        format,
+                        index,
+                        source,
+                        decoded
+                    );
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use image::{DynamicImage, GrayAlphaImage, LumaA};
+use pmd_cte::sir0::{is_sir0, unwrap_sir0, wrap_sir0};
+use pmd_cte::{CteDecodeError, CteFormat, CteImage};
+
+#[test]
+fn wrap_unwrap_round_trip() {
+    for len in [0, 1, 15, 16, 17, 100] {
+        let content: Vec<u8> = (0..len).map(|v| v as u8).collect();
+        let wrapped = wrap_sir0(&content);
+        assert!(is_sir0(&wrapped));
+        assert_eq!(wrapped.len() % 16, 0);
+        let unwrapped = unwrap_sir0(&wrapped).unwrap();
+        // the content is followed by its alignment padding
+        assert_eq!(unwrapped[..len], content[..]);
+        assert!(unwrapped.len() - len < 16);
+        assert!(unwrapped[len..].iter().all(|byte| *byte == 0xAA));
+    }
+}
+
+#[test]
+fn image_round_trip() {
+    let image = GrayAlphaImage::from_fn(16, 8, |x, y| LumaA([255, (x * 16 + y) as u8]));
+    let image = CteImage::new(CteFormat::A8, DynamicImage::ImageLumaA8(image));
+    let mut wrapped = Vec::new();
+    image.encode_cte_sir0(&mut wrapped).unwrap();
+    let decoded = CteImage::decode_cte_sir0(&wrapped).unwrap();
+    assert_eq!(decoded.image.to_rgba8(), image.image.to_rgba8());
+    assert!(decoded.decode_warnings.is_empty());
+    // files without a container are also accepted
+    let decoded = CteImage::decode_cte_sir0(&image.encode_to_vec().unwrap()).unwrap();
+    assert_eq!(decoded.image.to_rgba8(), image.image.to_rgba8());
+}
+
+#[test]
+fn content_pointer_outside_of_the_file() {
+    let mut wrapped = wrap_sir0(&[1, 2, 3]);
+    for content_start in [wrapped.len() as u32 + 1, u32::MAX, 8] {
+        wrapped[4..8].copy_from_slice(&content_start.to_le_bytes());
+        assert!(matches!(
+            unwrap_sir0(&wrapped),
+            Err(CteDecodeError::Sir0PointerInvalid(start, 4)) if start == content_start
+        ));
+    }
+}
+
+#[test]
+fn truncated_header() {
+    assert!(matches!(
+        unwrap_sir0(b"SIR0\x10\0\0\0"),
+        Err(CteDecodeError::UnexpectedEnd(8))
+    ));
+}
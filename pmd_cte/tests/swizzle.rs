@@ -0,0 +1,45 @@
+use pmd_cte::swizzle::*;
+
+#[test]
+fn first_pixels_are_at_the_bottom_left() {
+    assert_eq!(tile_coordinate(0), (0, 7));
+    assert_eq!(tile_coordinate(1), (1, 7));
+    assert_eq!(tile_coordinate(2), (0, 6));
+    assert_eq!(tile_coordinate(3), (1, 6));
+    assert_eq!(tile_coordinate(4), (2, 7));
+    assert_eq!(tile_coordinate(63), (7, 0));
+}
+
+#[test]
+fn coordinate_mapping_is_bijective() {
+    let mut seen = [false; 64];
+    for index in 0..64 {
+        let (x, y) = tile_coordinate(index);
+        assert!(x < 8 && y < 8);
+        assert_eq!(tile_index(x, y), index);
+        assert_eq!(morton_index(x, 7 - y), index);
+        assert!(!seen[(y * 8 + x) as usize]);
+        seen[(y * 8 + x) as usize] = true;
+    }
+}
+
+#[test]
+fn read_in_image_order_follow_tile_coordinate() {
+    let tile: [usize; 64] = std::array::from_fn(|i| i);
+    let mut count = 0;
+    read_in_image_order(&tile, |x, y, index| {
+        assert_eq!(index, count);
+        assert_eq!(tile_coordinate(index), (x, y));
+        count += 1;
+    });
+    assert_eq!(count, 64);
+}
+
+#[test]
+fn linear_and_tile_roundtrip() {
+    let linear: [u32; 64] = std::array::from_fn(|i| i as u32);
+    let tile = linear_to_tile(&linear);
+    assert_eq!(tile[0], 7 * 8);
+    assert_eq!(tile[63], 7);
+    assert_eq!(tile_to_linear(&tile), linear);
+}
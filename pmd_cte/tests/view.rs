@@ -0,0 +1,59 @@
+use pmd_cte::{encode_rgba8, CteFormat, CteImage, CteView, EncodeOptions};
+use std::io::Cursor;
+
+/// a 24×16 image where every pixel is different, encoded in the given format
+fn encoded_image(format: CteFormat) -> Vec<u8> {
+    let pixels: Vec<u8> = (0..24 * 16)
+        .flat_map(|index: u32| {
+            let (x, y) = (index % 24, index / 24);
+            [
+                (x * 10) as u8,
+                (y * 15) as u8,
+                (x * y) as u8,
+                (index * 7) as u8,
+            ]
+        })
+        .collect();
+    encode_rgba8(&pixels, 24, 16, format, &EncodeOptions::default()).unwrap()
+}
+
+#[test]
+fn tiles_match_decode_cte() {
+    for format in [CteFormat::Rgba8888, CteFormat::La4, CteFormat::Etc1A4] {
+        let data = encoded_image(format);
+        let decoded = CteImage::decode_cte(&mut Cursor::new(&data))
+            .unwrap()
+            .image
+            .to_rgba8();
+        let view = CteView::new(&data).unwrap();
+        assert_eq!(view.dimensions(), (24, 16));
+        for y in 0..16 {
+            for x in 0..24 {
+                assert_eq!(
+                    view.get_pixel(x, y),
+                    Some(decoded.get_pixel(x, y).0),
+                    "{:?}: pixel {}, {}",
+                    format,
+                    x,
+                    y
+                );
+                // any pixel of a tile give the whole tile
+                let tile = view.tile(x, y).unwrap();
+                let (tile_x, tile_y) = (x / 8 * 8, y / 8 * 8);
+                for (index, pixel) in tile.iter().enumerate() {
+                    let index = index as u32;
+                    assert_eq!(
+                        *pixel,
+                        decoded.get_pixel(tile_x + index % 8, tile_y + index / 8).0,
+                        "{:?}: tile of the pixel {}, {}",
+                        format,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+        assert_eq!(view.get_pixel(24, 0), None);
+        assert_eq!(view.tile(0, 16), None);
+    }
+}
@@ -0,0 +1,24 @@
+//! Generate `pmd_cte.h` in `OUT_DIR` from the functions and types of `src/lib.rs`. With the
+//! `PMD_CTE_UPDATE_HEADER` environment variable set, also update `include/pmd_cte.h`.
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=PMD_CTE_UPDATE_HEADER");
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("cbindgen.toml should be valid");
+    let bindings = cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("the header should be generated");
+    bindings.write_to_file(out_dir.join("pmd_cte.h"));
+    if env::var_os("PMD_CTE_UPDATE_HEADER").is_some() {
+        bindings.write_to_file(crate_dir.join("include/pmd_cte.h"));
+    };
+}
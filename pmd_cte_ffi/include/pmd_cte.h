@@ -0,0 +1,127 @@
+/* C interface to pmd_cte, to decode and encode the cte files of pokemon super mystery dungeon.
+ *
+ * Every function return CTE_OK or a negative error code, described by cte_error_message. The
+ * buffers allocated by the library should be freed with the matching *_free function. */
+
+#ifndef PMD_CTE_H
+#define PMD_CTE_H
+
+/* Generated by cbindgen from src/lib.rs. Don't modify it manually, but build the crate with the PMD_CTE_UPDATE_HEADER environment variable set. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define CTE_OK 0
+
+// one of the pointer given is null
+#define CTE_ERROR_NULL_POINTER -1
+
+// the file is truncated or its header is invalid
+#define CTE_ERROR_INVALID_FILE -2
+
+// the file use a format that isn't supported
+#define CTE_ERROR_UNSUPPORTED_FORMAT -3
+
+// the dimensions of the image aren't multiples of 8, or don't match the length of the pixels
+#define CTE_ERROR_INVALID_DIMENSIONS -4
+
+// the format id given to encode isn't one of a built-in format
+#define CTE_ERROR_UNKNOWN_FORMAT -5
+
+// the image couldn't be encoded for another reason
+#define CTE_ERROR_ENCODE -6
+
+// a bug in this library
+#define CTE_ERROR_INTERNAL -7
+
+// the ids of the formats, as stored in the header
+#define CTE_FORMAT_RGBA8888 0
+
+#define CTE_FORMAT_RGB888 1
+
+#define CTE_FORMAT_RGBA5551 2
+
+#define CTE_FORMAT_RGB565 3
+
+#define CTE_FORMAT_RGBA4444 4
+
+#define CTE_FORMAT_LA8 5
+
+#define CTE_FORMAT_HILO8 6
+
+#define CTE_FORMAT_L8 7
+
+#define CTE_FORMAT_A8 8
+
+#define CTE_FORMAT_LA4 9
+
+#define CTE_FORMAT_L4 10
+
+#define CTE_FORMAT_A4 11
+
+#define CTE_FORMAT_ETC1 12
+
+#define CTE_FORMAT_ETC1A4 13
+
+// A decoded image. `pixels` contain `pixels_len` bytes of rgba pixels, row by row.
+typedef struct CteDecodedImage {
+  uint32_t width;
+  uint32_t height;
+  uint32_t format_id;
+  uint8_t *pixels;
+  size_t pixels_len;
+} CteDecodedImage;
+
+// An encoded cte file
+typedef struct CteBuffer {
+  uint8_t *data;
+  size_t len;
+} CteBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// decode the main image of the cte file stored in the `len` bytes at `buf` into `out`. The
+// pixels should then be freed with `cte_decoded_image_free`.
+//
+// # Safety
+//
+// `buf` should point to `len` readable bytes, and `out` to a writable `CteDecodedImage`.
+int32_t cte_decode(const uint8_t *buf, size_t len, struct CteDecodedImage *out);
+
+// free the pixels of an image decoded by `cte_decode`. Does nothing if they are null.
+//
+// # Safety
+//
+// `image` should be null, or have been filled by `cte_decode` and not freed yet.
+void cte_decoded_image_free(struct CteDecodedImage *image);
+
+// encode `len` bytes of rgba pixels, row by row, of a `width`×`height` image in the format
+// with the id `format_id` into `out`. The file should then be freed with `cte_buffer_free`.
+//
+// # Safety
+//
+// `pixels` should point to `len` readable bytes, and `out` to a writable `CteBuffer`.
+int32_t cte_encode(const uint8_t *pixels,
+                   size_t len,
+                   uint32_t width,
+                   uint32_t height,
+                   uint32_t format_id,
+                   struct CteBuffer *out);
+
+// free a file encoded by `cte_encode`. Does nothing if it is null.
+//
+// # Safety
+//
+// `buffer` should be null, or have been filled by `cte_encode` and not freed yet.
+void cte_buffer_free(struct CteBuffer *buffer);
+
+// a static, nul terminated description of an error code
+const char *cte_error_message(int32_t code);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PMD_CTE_H */
@@ -0,0 +1,210 @@
+//! A C interface to decode and encode cte files in memory. The declarations are in
+//! `include/pmd_cte.h`, generated by cbindgen when the crate is built.
+//!
+//! Every function return `CTE_OK` or a negative error code, whose description is given by
+//! `cte_error_message`. The buffers allocated by this library should be freed with the matching
+//! `*_free` function.
+
+use pmd_cte::{decode_rgba8, encode_rgba8, CteDecodeError, CteEncodeError, CteFormat};
+use pmd_cte::{DecodeOptions, EncodeOptions};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+
+pub const CTE_OK: i32 = 0;
+/// one of the pointer given is null
+pub const CTE_ERROR_NULL_POINTER: i32 = -1;
+/// the file is truncated or its header is invalid
+pub const CTE_ERROR_INVALID_FILE: i32 = -2;
+/// the file use a format that isn't supported
+pub const CTE_ERROR_UNSUPPORTED_FORMAT: i32 = -3;
+/// the dimensions of the image aren't multiples of 8, or don't match the length of the pixels
+pub const CTE_ERROR_INVALID_DIMENSIONS: i32 = -4;
+/// the format id given to encode isn't one of a built-in format
+pub const CTE_ERROR_UNKNOWN_FORMAT: i32 = -5;
+/// the image couldn't be encoded for another reason
+pub const CTE_ERROR_ENCODE: i32 = -6;
+/// a bug in this library
+pub const CTE_ERROR_INTERNAL: i32 = -7;
+
+/// the ids of the formats, as stored in the header
+pub const CTE_FORMAT_RGBA8888: u32 = 0;
+pub const CTE_FORMAT_RGB888: u32 = 1;
+pub const CTE_FORMAT_RGBA5551: u32 = 2;
+pub const CTE_FORMAT_RGB565: u32 = 3;
+pub const CTE_FORMAT_RGBA4444: u32 = 4;
+pub const CTE_FORMAT_LA8: u32 = 5;
+pub const CTE_FORMAT_HILO8: u32 = 6;
+pub const CTE_FORMAT_L8: u32 = 7;
+pub const CTE_FORMAT_A8: u32 = 8;
+pub const CTE_FORMAT_LA4: u32 = 9;
+pub const CTE_FORMAT_L4: u32 = 10;
+pub const CTE_FORMAT_A4: u32 = 11;
+pub const CTE_FORMAT_ETC1: u32 = 12;
+pub const CTE_FORMAT_ETC1A4: u32 = 13;
+
+/// A decoded image. `pixels` contain `pixels_len` bytes of rgba pixels, row by row.
+#[repr(C)]
+pub struct CteDecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format_id: u32,
+    pub pixels: *mut u8,
+    pub pixels_len: usize,
+}
+
+/// An encoded cte file
+#[repr(C)]
+pub struct CteBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+fn decode_error_code(err: &CteDecodeError) -> i32 {
+    match err {
+        CteDecodeError::UnsuportedFormat(_, _) | CteDecodeError::PixelLenghtInvalid(_, _, _) => {
+            CTE_ERROR_UNSUPPORTED_FORMAT
+        }
+        CteDecodeError::InternalError(_, _) => CTE_ERROR_INTERNAL,
+        _ => CTE_ERROR_INVALID_FILE,
+    }
+}
+
+fn encode_error_code(err: &CteEncodeError) -> i32 {
+    match err {
+        CteEncodeError::WidthNotMultiple8(_)
+        | CteEncodeError::HeightNotMultiple8(_)
+        | CteEncodeError::PixelBufferSizeInvalid(_, _)
+        | CteEncodeError::ImageTooBig(_, _) => CTE_ERROR_INVALID_DIMENSIONS,
+        _ => CTE_ERROR_ENCODE,
+    }
+}
+
+/// run `f`, returning `CTE_ERROR_INTERNAL` if it panic, as unwinding into C is undefined. Nothing
+/// it modify is used after a panic.
+fn guard(f: impl FnOnce() -> i32) -> i32 {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(CTE_ERROR_INTERNAL)
+}
+
+/// give the ownership of `data` to the caller, returning its pointer and length
+fn into_raw(data: Vec<u8>) -> (*mut u8, usize) {
+    let len = data.len();
+    (Box::into_raw(data.into_boxed_slice()) as *mut u8, len)
+}
+
+/// free data returned by [`into_raw`]
+unsafe fn free_raw(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    };
+}
+
+/// decode the main image of the cte file stored in the `len` bytes at `buf` into `out`. The
+/// pixels should then be freed with `cte_decoded_image_free`.
+///
+/// # Safety
+///
+/// `buf` should point to `len` readable bytes, and `out` to a writable `CteDecodedImage`.
+#[no_mangle]
+pub unsafe extern "C" fn cte_decode(buf: *const u8, len: usize, out: *mut CteDecodedImage) -> i32 {
+    if buf.is_null() || out.is_null() {
+        return CTE_ERROR_NULL_POINTER;
+    };
+    let data = slice::from_raw_parts(buf, len);
+    guard(|| match decode_rgba8(data, &DecodeOptions::default()) {
+        Ok((header, pixels)) => {
+            let (pixels, pixels_len) = into_raw(pixels);
+            *out = CteDecodedImage {
+                width: header.width,
+                height: header.height,
+                format_id: header.format_id,
+                pixels,
+                pixels_len,
+            };
+            CTE_OK
+        }
+        Err(err) => decode_error_code(&err),
+    })
+}
+
+/// free the pixels of an image decoded by `cte_decode`. Does nothing if they are null.
+///
+/// # Safety
+///
+/// `image` should be null, or have been filled by `cte_decode` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cte_decoded_image_free(image: *mut CteDecodedImage) {
+    if let Some(image) = image.as_mut() {
+        free_raw(image.pixels, image.pixels_len);
+        image.pixels = ptr::null_mut();
+        image.pixels_len = 0;
+    };
+}
+
+/// encode `len` bytes of rgba pixels, row by row, of a `width`×`height` image in the format
+/// with the id `format_id` into `out`. The file should then be freed with `cte_buffer_free`.
+///
+/// # Safety
+///
+/// `pixels` should point to `len` readable bytes, and `out` to a writable `CteBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn cte_encode(
+    pixels: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    format_id: u32,
+    out: *mut CteBuffer,
+) -> i32 {
+    if pixels.is_null() || out.is_null() {
+        return CTE_ERROR_NULL_POINTER;
+    };
+    let format = match CteFormat::from_id(format_id) {
+        Some(format) => format,
+        None => return CTE_ERROR_UNKNOWN_FORMAT,
+    };
+    let pixels = slice::from_raw_parts(pixels, len);
+    guard(
+        || match encode_rgba8(pixels, width, height, format, &EncodeOptions::default()) {
+            Ok(encoded) => {
+                let (data, len) = into_raw(encoded);
+                *out = CteBuffer { data, len };
+                CTE_OK
+            }
+            Err(err) => encode_error_code(&err),
+        },
+    )
+}
+
+/// free a file encoded by `cte_encode`. Does nothing if it is null.
+///
+/// # Safety
+///
+/// `buffer` should be null, or have been filled by `cte_encode` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cte_buffer_free(buffer: *mut CteBuffer) {
+    if let Some(buffer) = buffer.as_mut() {
+        free_raw(buffer.data, buffer.len);
+        buffer.data = ptr::null_mut();
+        buffer.len = 0;
+    };
+}
+
+/// a static, nul terminated description of an error code
+#[no_mangle]
+pub extern "C" fn cte_error_message(code: i32) -> *const c_char {
+    let message: &'static [u8] = match code {
+        CTE_OK => b"no error\0",
+        CTE_ERROR_NULL_POINTER => b"a null pointer was given\0",
+        CTE_ERROR_INVALID_FILE => b"the cte file is truncated or its header is invalid\0",
+        CTE_ERROR_UNSUPPORTED_FORMAT => b"the cte file use a format that isn't supported\0",
+        CTE_ERROR_INVALID_DIMENSIONS => {
+            b"the dimensions of the image aren't multiples of 8, or don't match the pixels\0"
+        }
+        CTE_ERROR_UNKNOWN_FORMAT => b"the format id doesn't correspond to a known format\0",
+        CTE_ERROR_ENCODE => b"the image couldn't be encoded\0",
+        CTE_ERROR_INTERNAL => b"internal error\0",
+        _ => b"unknown error code\0",
+    };
+    message.as_ptr() as *const c_char
+}
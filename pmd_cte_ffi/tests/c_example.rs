@@ -0,0 +1,65 @@
+//! Compile `example.c` with the C compiler of the system (or the one in `CC`), link it to the
+//! shared library, and run it
+
+#![cfg(unix)]
+
+use std::env;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_example() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // the test binary is in the deps folder, next to which cargo put the libraries
+    let target_dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .to_path_buf();
+    // cargo doesn't build the shared library for the tests, as they can't use it directly
+    let mut build = Command::new(env!("CARGO"));
+    build
+        .args([
+            "build",
+            "--quiet",
+            "--lib",
+            "-p",
+            "pmd_cte_ffi",
+            "--target-dir",
+        ])
+        .arg(target_dir.parent().unwrap());
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    };
+    assert!(
+        build.status().unwrap().success(),
+        "the library didn't build"
+    );
+    let library = target_dir.join(format!("{}pmd_cte_ffi{}", DLL_PREFIX, DLL_SUFFIX));
+    let executable = target_dir.join("pmd_cte_ffi_example");
+
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg(crate_dir.join("tests/example.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(&library)
+        .arg(format!("-Wl,-rpath,{}", target_dir.display()))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-o")
+        .arg(&executable)
+        .status()
+        .expect("the C compiler should be installed");
+    assert!(status.success(), "example.c didn't compile");
+
+    let output = Command::new(&executable).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
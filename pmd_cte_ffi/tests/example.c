@@ -0,0 +1,52 @@
+/* Encode a gradient in every format, and check it decode back to an image of the same size. */
+
+#include "pmd_cte.h"
+#include <stdio.h>
+#include <stdlib.h>
+
+#define WIDTH 16
+#define HEIGHT 8
+
+int main(void) {
+    const uint32_t formats[] = {CTE_FORMAT_RGBA8888, CTE_FORMAT_RGB888, CTE_FORMAT_RGB565,
+                                CTE_FORMAT_LA8,      CTE_FORMAT_L8,     CTE_FORMAT_A8,
+                                CTE_FORMAT_LA4,      CTE_FORMAT_L4,     CTE_FORMAT_A4,
+                                CTE_FORMAT_ETC1,     CTE_FORMAT_ETC1A4};
+    uint8_t pixels[WIDTH * HEIGHT * 4];
+    for (size_t i = 0; i < sizeof(pixels); i++) {
+        pixels[i] = (uint8_t)(i * 2);
+    }
+
+    for (size_t i = 0; i < sizeof(formats) / sizeof(formats[0]); i++) {
+        CteBuffer file;
+        int32_t result = cte_encode(pixels, sizeof(pixels), WIDTH, HEIGHT, formats[i], &file);
+        if (result != CTE_OK) {
+            fprintf(stderr, "encoding in the format %u failed: %s\n", formats[i],
+                    cte_error_message(result));
+            return 1;
+        }
+        CteDecodedImage image;
+        result = cte_decode(file.data, file.len, &image);
+        cte_buffer_free(&file);
+        if (result != CTE_OK) {
+            fprintf(stderr, "decoding the format %u failed: %s\n", formats[i],
+                    cte_error_message(result));
+            return 1;
+        }
+        if (image.width != WIDTH || image.height != HEIGHT || image.format_id != formats[i] ||
+            image.pixels_len != sizeof(pixels)) {
+            fprintf(stderr, "the format %u was decoded as a %ux%u image in the format %u\n",
+                    formats[i], image.width, image.height, image.format_id);
+            return 1;
+        }
+        cte_decoded_image_free(&image);
+    }
+
+    CteDecodedImage image;
+    if (cte_decode(pixels, 4, &image) != CTE_ERROR_INVALID_FILE) {
+        fprintf(stderr, "a truncated file was decoded\n");
+        return 1;
+    }
+    printf("ok\n");
+    return 0;
+}
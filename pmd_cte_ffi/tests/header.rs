@@ -0,0 +1,16 @@
+//! Check that the header in `include/` match the one generated by the build script
+
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/pmd_cte.h"));
+    let checked_in =
+        fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("include/pmd_cte.h"))
+            .unwrap();
+    assert!(
+        generated == checked_in,
+        "include/pmd_cte.h is outdated, build the crate with PMD_CTE_UPDATE_HEADER=1 to update it"
+    );
+}
@@ -0,0 +1,19 @@
+/* tslint:disable */
+/* eslint-disable */
+
+/** A decoded image, with its rgba pixels row by row */
+export interface DecodedImage {
+  width: number
+  height: number
+  /** the name of the format the image was stored in, or null if it isn't a built-in one */
+  format?: string | null
+  pixels: Buffer
+}
+/** decode the main image of a cte file */
+export function decode(data: Buffer): Promise<DecodedImage>
+/**
+ * encode rgba pixels, row by row, in the format with the given name (like "a8" or
+ * "rgba8888"), "a8" by default. With `pad`, images whose dimensions aren't multiples of 8 are
+ * padded with transparent pixels.
+ */
+export function encode(pixels: Buffer, width: number, height: number, format?: string | undefined | null, pad?: boolean | undefined | null): Promise<Buffer>
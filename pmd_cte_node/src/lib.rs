@@ -0,0 +1,106 @@
+//! Node.js bindings to decode and encode cte files. The functions return promises, the work
+//! being done in the thread pool of node.
+
+use napi::bindgen_prelude::{AsyncTask, Buffer};
+use napi::{Env, Error, Result, Status, Task};
+use napi_derive::napi;
+use pmd_cte::{decode_rgba8, encode_rgba8, CteFormat, DecodeOptions, EncodeOptions};
+
+/// A decoded image, with its rgba pixels row by row
+#[napi(object)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    /// the name of the format the image was stored in, or null if it isn't a built-in one
+    pub format: Option<String>,
+    pub pixels: Buffer,
+}
+
+pub struct DecodeTask {
+    data: Vec<u8>,
+}
+
+impl Task for DecodeTask {
+    type Output = (u32, u32, u32, Vec<u8>);
+    type JsValue = DecodedImage;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let (header, pixels) = decode_rgba8(&self.data, &DecodeOptions::default())
+            .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))?;
+        Ok((header.width, header.height, header.format_id, pixels))
+    }
+
+    fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue> {
+        let (width, height, format_id, pixels) = output;
+        Ok(DecodedImage {
+            width,
+            height,
+            format: CteFormat::from_id(format_id)
+                .and_then(|format| format.name())
+                .map(str::to_string),
+            pixels: pixels.into(),
+        })
+    }
+}
+
+/// decode the main image of a cte file
+#[napi(ts_return_type = "Promise<DecodedImage>")]
+pub fn decode(data: Buffer) -> AsyncTask<DecodeTask> {
+    AsyncTask::new(DecodeTask {
+        data: data.to_vec(),
+    })
+}
+
+pub struct EncodeTask {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: CteFormat,
+    options: EncodeOptions,
+}
+
+impl Task for EncodeTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        encode_rgba8(
+            &self.pixels,
+            self.width,
+            self.height,
+            self.format,
+            &self.options,
+        )
+        .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))
+    }
+
+    fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+/// encode rgba pixels, row by row, in the format with the given name (like "a8" or
+/// "rgba8888"), "a8" by default. With `pad`, images whose dimensions aren't multiples of 8 are
+/// padded with transparent pixels.
+#[napi(ts_return_type = "Promise<Buffer>")]
+pub fn encode(
+    pixels: Buffer,
+    width: u32,
+    height: u32,
+    format: Option<String>,
+    pad: Option<bool>,
+) -> Result<AsyncTask<EncodeTask>> {
+    let format: CteFormat = format.as_deref().unwrap_or("a8").parse().map_err(
+        |err: pmd_cte::ParseCteFormatError| Error::new(Status::InvalidArg, err.to_string()),
+    )?;
+    Ok(AsyncTask::new(EncodeTask {
+        pixels: pixels.to_vec(),
+        width,
+        height,
+        format,
+        options: EncodeOptions {
+            pad: pad.unwrap_or(false),
+            ..EncodeOptions::default()
+        },
+    }))
+}
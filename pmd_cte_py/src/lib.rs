@@ -0,0 +1,141 @@
+//! Python bindings to decode and encode cte files. The decoded images expose the numpy array
+//! interface, so they can be given to `PIL.Image.fromarray` or `numpy.asarray`.
+
+use pmd_cte::{decode_rgba8, encode_rgba8, CteFormat, DecodeOptions, EncodeOptions};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+create_exception!(
+    pmd_cte_py,
+    CteError,
+    PyException,
+    "the file couldn't be decoded, or the image encoded"
+);
+
+/// A decoded image, with its rgba pixels row by row
+#[pyclass(frozen)]
+struct DecodedImage {
+    #[pyo3(get)]
+    width: u32,
+    #[pyo3(get)]
+    height: u32,
+    /// the name of the format the image was stored in, or None if it isn't a built-in one
+    #[pyo3(get)]
+    format: Option<&'static str>,
+    pixels: Py<PyBytes>,
+}
+
+#[pymethods]
+impl DecodedImage {
+    /// the pil mode of the pixels
+    #[getter]
+    fn mode(&self) -> &'static str {
+        "RGBA"
+    }
+
+    #[getter]
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// the rgba pixels, row by row
+    fn tobytes(&self, py: Python<'_>) -> Py<PyBytes> {
+        self.pixels.clone_ref(py)
+    }
+
+    #[getter]
+    fn __array_interface__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let interface = PyDict::new(py);
+        interface.set_item("version", 3)?;
+        interface.set_item("shape", (self.height, self.width, 4))?;
+        interface.set_item("typestr", "|u1")?;
+        // with strides, pil copy the pixels with `tobytes` instead of requiring a buffer
+        interface.set_item("strides", (self.width as usize * 4, 4, 1))?;
+        interface.set_item("data", self.pixels.clone_ref(py))?;
+        Ok(interface)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DecodedImage(width={}, height={}, format={:?})",
+            self.width,
+            self.height,
+            self.format.unwrap_or("unknown")
+        )
+    }
+}
+
+/// decode the main image of a cte file
+#[pyfunction]
+fn decode(py: Python<'_>, data: &[u8]) -> PyResult<DecodedImage> {
+    let (header, pixels) = decode_rgba8(data, &DecodeOptions::default())
+        .map_err(|err| CteError::new_err(err.to_string()))?;
+    Ok(DecodedImage {
+        width: header.width,
+        height: header.height,
+        format: CteFormat::from_id(header.format_id).and_then(|format| format.name()),
+        pixels: PyBytes::new(py, &pixels).unbind(),
+    })
+}
+
+/// the dimensions and rgba pixels of an object with the array interface of shape
+/// (height, width, 4) of uint8, or of a pil image
+fn rgba_pixels(image: &Bound<'_, PyAny>) -> PyResult<(u32, u32, Vec<u8>)> {
+    let (width, height, pixels) = if image.hasattr("__array_interface__")? {
+        let interface = image.getattr("__array_interface__")?;
+        let shape: Vec<u32> = interface.get_item("shape")?.extract()?;
+        let typestr: String = interface.get_item("typestr")?.extract()?;
+        if shape.len() != 3 || shape[2] != 4 || typestr != "|u1" {
+            return Err(PyValueError::new_err(format!(
+                "expected an array of shape (height, width, 4) of uint8, got shape {:?} of {}",
+                shape, typestr
+            )));
+        };
+        (shape[1], shape[0], image.call_method0("tobytes")?)
+    } else {
+        let rgba = image.call_method1("convert", ("RGBA",))?;
+        let (width, height) = rgba.getattr("size")?.extract()?;
+        (width, height, rgba.call_method0("tobytes")?)
+    };
+    Ok((
+        width,
+        height,
+        pixels.downcast::<PyBytes>()?.as_bytes().to_vec(),
+    ))
+}
+
+/// encode an image in the format with the given name (like "a8" or "rgba8888"). The image is
+/// either an array of shape (height, width, 4) of uint8 or a pil image. With `pad`, images whose
+/// dimensions aren't multiples of 8 are padded with transparent pixels.
+#[pyfunction]
+#[pyo3(signature = (image, format = "a8", pad = false))]
+fn encode<'py>(
+    py: Python<'py>,
+    image: &Bound<'py, PyAny>,
+    format: &str,
+    pad: bool,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let format: CteFormat = format
+        .parse()
+        .map_err(|err: pmd_cte::ParseCteFormatError| PyValueError::new_err(err.to_string()))?;
+    let (width, height, pixels) = rgba_pixels(image)?;
+    let options = EncodeOptions {
+        pad,
+        ..EncodeOptions::default()
+    };
+    let encoded = py
+        .allow_threads(|| encode_rgba8(&pixels, width, height, format, &options))
+        .map_err(|err| CteError::new_err(err.to_string()))?;
+    Ok(PyBytes::new(py, &encoded))
+}
+
+#[pymodule]
+fn pmd_cte_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("CteError", m.py().get_type::<CteError>())?;
+    m.add_class::<DecodedImage>()?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    m.add_function(wrap_pyfunction!(encode, m)?)?;
+    Ok(())
+}